@@ -0,0 +1,124 @@
+// Local files attached to a chat as document content blocks
+//
+// The file dialog is opened from here, so the webview can only attach files
+// the user picked, never name a path itself. Reading, validating and base64
+// encoding happen here too; the finished block goes back to the frontend,
+// which keeps it in the conversation and sends it with every chat request.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::info;
+use std::fs;
+use std::path::Path;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::providers::{ContentBlock, DocumentCitations, DocumentSource};
+
+// Claude takes requests up to 32 MB, and base64 grows a PDF by a third
+const MAX_PDF_BYTES: u64 = 24 * 1024 * 1024;
+const MAX_TEXT_BYTES: u64 = 2 * 1024 * 1024;
+
+const TEXT_EXTENSIONS: [&str; 10] = [
+    "txt", "md", "markdown", "csv", "tsv", "json", "xml", "html", "log", "yaml",
+];
+
+/// Reads `path` into a document block: PDFs as base64, text files inline.
+pub fn document_block(
+    path: &Path,
+    title: Option<String>,
+    citations: bool,
+) -> Result<ContentBlock, String> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    let is_pdf = extension == "pdf";
+    if !is_pdf && !TEXT_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "Unsupported document type '.{}'. Use a PDF or a text file ({}).",
+            extension,
+            TEXT_EXTENSIONS.join(", ")
+        ));
+    }
+
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let limit = if is_pdf {
+        MAX_PDF_BYTES
+    } else {
+        MAX_TEXT_BYTES
+    };
+    if size > limit {
+        return Err(format!(
+            "{} is too large ({:.1} MB, limit {} MB)",
+            path.display(),
+            size as f64 / (1024.0 * 1024.0),
+            limit / (1024 * 1024)
+        ));
+    }
+
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let source = if is_pdf {
+        if !bytes.starts_with(b"%PDF-") {
+            return Err(format!("{} is not a valid PDF file", path.display()));
+        }
+        DocumentSource {
+            source_type: "base64".to_string(),
+            media_type: "application/pdf".to_string(),
+            data: STANDARD.encode(&bytes),
+        }
+    } else {
+        DocumentSource {
+            source_type: "text".to_string(),
+            media_type: "text/plain".to_string(),
+            data: String::from_utf8(bytes)
+                .map_err(|_| format!("{} is not a UTF-8 text file", path.display()))?,
+        }
+    };
+
+    let title = title.or_else(|| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.to_string())
+    });
+
+    Ok(ContentBlock::Document {
+        source,
+        title,
+        citations: citations.then_some(DocumentCitations { enabled: true }),
+    })
+}
+
+// Commands
+
+/// Lets the user pick a PDF or text file and builds a document block from
+/// it, or returns `None` when the dialog is dismissed. `citations` asks
+/// Claude to cite passages of the document.
+#[tauri::command]
+pub async fn pick_document(
+    app: tauri::AppHandle,
+    citations: Option<bool>,
+) -> Result<Option<ContentBlock>, String> {
+    let extensions: Vec<&str> = std::iter::once("pdf").chain(TEXT_EXTENSIONS).collect();
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Document", &extensions)
+        .pick_file(move |path| {
+            let _ = sender.send(path);
+        });
+
+    let Some(path) = receiver
+        .await
+        .map_err(|_| "The file dialog closed unexpectedly".to_string())?
+    else {
+        return Ok(None);
+    };
+    let path = path
+        .into_path()
+        .map_err(|e| format!("Unsupported file location: {}", e))?;
+
+    info!("Loading document {}", path.display());
+    document_block(&path, None, citations.unwrap_or(false)).map(Some)
+}
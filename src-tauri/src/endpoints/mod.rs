@@ -0,0 +1,226 @@
+// User-defined OpenAI-compatible endpoints
+//
+// LM Studio, llama.cpp server, vLLM, OpenRouter and friends all speak the
+// chat completions API, so each registered endpoint is served by the OpenAI
+// provider with its own base URL. The list lives in ~/.olly/endpoints.json;
+// API keys go through the regular key storage under `endpoint_<id>`.
+
+use futures_util::future::join_all;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::providers::{LlmProvider, OpenAiProvider};
+
+/// Prefix that marks an endpoint in provider ids (`endpoint:<id>`).
+pub const PROVIDER_PREFIX: &str = "endpoint:";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OpenAiEndpoint {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+}
+
+impl OpenAiEndpoint {
+    pub fn provider_id(&self) -> String {
+        format!("{}{}", PROVIDER_PREFIX, self.id)
+    }
+
+    pub fn key_provider(&self) -> String {
+        format!("endpoint_{}", self.id)
+    }
+
+    pub fn provider(&self) -> OpenAiProvider {
+        OpenAiProvider::compatible(self)
+    }
+}
+
+fn get_endpoints_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".olly").join("endpoints.json")
+}
+
+pub fn load_endpoints() -> Vec<OpenAiEndpoint> {
+    match fs::read_to_string(get_endpoints_path()) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            error!("Failed to parse endpoints file, ignoring it: {}", e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_endpoints(endpoints: &[OpenAiEndpoint]) -> Result<(), String> {
+    let path = get_endpoints_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let data = serde_json::to_string_pretty(endpoints)
+        .map_err(|e| format!("Failed to serialize endpoints: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write endpoints: {}", e))
+}
+
+/// Looks up an endpoint by provider id (`endpoint:<id>`).
+pub fn find_endpoint(provider_id: &str) -> Option<OpenAiEndpoint> {
+    let id = provider_id.strip_prefix(PROVIDER_PREFIX)?;
+    load_endpoints()
+        .into_iter()
+        .find(|endpoint| endpoint.id == id)
+}
+
+/// Servers are registered by their API root, e.g. `http://localhost:1234/v1`.
+fn normalize_base_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid endpoint URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!(
+            "Invalid endpoint URL scheme '{}', expected http or https",
+            parsed.scheme()
+        ));
+    }
+    Ok(url.to_string())
+}
+
+/// Lowercase slug of the display name, made unique among existing ids.
+fn make_id(name: &str, endpoints: &[OpenAiEndpoint]) -> String {
+    let slug = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() {
+        "endpoint".to_string()
+    } else {
+        slug
+    };
+
+    let mut id = slug.clone();
+    let mut n = 2;
+    while endpoints.iter().any(|endpoint| endpoint.id == id) {
+        id = format!("{}-{}", slug, n);
+        n += 1;
+    }
+    id
+}
+
+/// All models from every registered endpoint, tagged with its provider id.
+/// Endpoints are asked at once, so one that is down only costs the timeout.
+pub async fn list_all_models() -> Vec<serde_json::Value> {
+    let endpoints = load_endpoints();
+    let results = join_all(
+        endpoints
+            .iter()
+            .map(|endpoint| async move { endpoint.provider().list_models().await }),
+    )
+    .await;
+
+    let mut models = Vec::new();
+    for (endpoint, result) in endpoints.iter().zip(results) {
+        match result {
+            Ok(endpoint_models) => models.extend(endpoint_models),
+            Err(e) => error!("Failed to list models for {}: {}", endpoint.name, e),
+        }
+    }
+    models
+}
+
+#[tauri::command]
+pub async fn get_openai_endpoints() -> Result<Vec<serde_json::Value>, String> {
+    Ok(load_endpoints()
+        .into_iter()
+        .map(|endpoint| {
+            let has_key = matches!(
+                crate::get_api_key_file(&endpoint.key_provider()),
+                Ok(Some(_))
+            );
+            serde_json::json!({
+                "id": endpoint.id,
+                "provider": endpoint.provider_id(),
+                "name": endpoint.name,
+                "base_url": endpoint.base_url,
+                "has_key": has_key
+            })
+        })
+        .collect())
+}
+
+/// Adds an endpoint, or updates it when `id` is given. An empty `api_key`
+/// leaves the stored key alone; use `clear_key` to remove it.
+#[tauri::command]
+pub async fn save_openai_endpoint(
+    id: Option<String>,
+    name: String,
+    base_url: String,
+    api_key: Option<String>,
+    clear_key: Option<bool>,
+) -> Result<OpenAiEndpoint, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Endpoint name cannot be empty".to_string());
+    }
+    let base_url = normalize_base_url(&base_url)?;
+
+    let mut endpoints = load_endpoints();
+    let endpoint = match id {
+        Some(id) => {
+            let Some(existing) = endpoints.iter_mut().find(|endpoint| endpoint.id == id) else {
+                return Err(format!("Endpoint not found: {}", id));
+            };
+            existing.name = name;
+            existing.base_url = base_url;
+            existing.clone()
+        }
+        None => {
+            let endpoint = OpenAiEndpoint {
+                id: make_id(&name, &endpoints),
+                name,
+                base_url,
+            };
+            endpoints.push(endpoint.clone());
+            endpoint
+        }
+    };
+
+    if clear_key.unwrap_or(false) {
+        crate::delete_api_key_file(&endpoint.key_provider())?;
+    } else if let Some(key) = api_key.filter(|k| !k.is_empty()) {
+        crate::store_api_key_file(&endpoint.key_provider(), &key)?;
+    }
+
+    save_endpoints(&endpoints)?;
+    info!("Saved endpoint {} ({})", endpoint.name, endpoint.base_url);
+    Ok(endpoint)
+}
+
+#[tauri::command]
+pub async fn delete_openai_endpoint(id: String) -> Result<(), String> {
+    let mut endpoints = load_endpoints();
+    let Some(index) = endpoints.iter().position(|endpoint| endpoint.id == id) else {
+        return Err(format!("Endpoint not found: {}", id));
+    };
+
+    let endpoint = endpoints.remove(index);
+    crate::delete_api_key_file(&endpoint.key_provider())?;
+    save_endpoints(&endpoints)?;
+    info!("Deleted endpoint {}", endpoint.name);
+    Ok(())
+}
+
+/// Fetches `/models` from one endpoint, surfacing errors so the settings
+/// screen can show why a server is not answering.
+#[tauri::command]
+pub async fn get_openai_endpoint_models(id: String) -> Result<Vec<serde_json::Value>, String> {
+    let endpoint = load_endpoints()
+        .into_iter()
+        .find(|endpoint| endpoint.id == id)
+        .ok_or_else(|| format!("Endpoint not found: {}", id))?;
+    endpoint.provider().fetch_models().await
+}
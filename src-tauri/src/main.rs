@@ -9,6 +9,22 @@ use std::fs;
 use std::path::PathBuf;
 use tauri::Emitter;
 
+mod documents;
+mod endpoints;
+mod ollama;
+mod profiles;
+mod providers;
+mod tools;
+mod usage;
+
+use profiles::ProfileStore;
+use providers::{
+    ChatRequest, ChatResponse, ClaudeProvider, GeminiProvider, GenerationOptions, LlmProvider,
+    Message, OllamaProvider, OpenAiProvider, PerplexityProvider, StreamEvent, StreamRegistry,
+    StreamSink,
+};
+use tauri::ipc::Channel;
+
 // API Key Management Module
 mod api_keys {
     use super::*;
@@ -385,7 +401,7 @@ async fn list_api_key_providers(_app: tauri::AppHandle) -> Result<Vec<String>, S
     let mut providers = Vec::new();
 
     // Check for known providers in file storage
-    for provider in ["claude", "perplexity", "openai"] {
+    for provider in ["claude", "perplexity", "openai", "gemini"] {
         if let Ok(Some(_)) = get_api_key_file(provider) {
             providers.push(provider.to_string());
         }
@@ -565,6 +581,45 @@ async fn validate_api_key(provider: String, api_key: String) -> Result<bool, Str
                 }
             }
         }
+        "gemini" => {
+            // Test Gemini API by listing models
+            let response = client
+                .get(format!("{}/models?pageSize=1", providers::GEMINI_BASE_URL))
+                .header("x-goog-api-key", &api_key)
+                .send()
+                .await;
+
+            match response {
+                Ok(resp) => {
+                    if resp.status().is_success() {
+                        info!("Gemini API key validation successful");
+                        Ok(true)
+                    } else if resp.status() == 400 || resp.status() == 401 || resp.status() == 403 {
+                        // Gemini reports an invalid key as 400 API_KEY_INVALID
+                        info!("Gemini API key validation failed - unauthorized");
+                        Ok(false)
+                    } else {
+                        let status = resp.status();
+                        let error_text = resp
+                            .text()
+                            .await
+                            .unwrap_or_else(|_| "Unknown error".to_string());
+                        error!(
+                            "Gemini API validation failed with status {}: {}",
+                            status, error_text
+                        );
+                        Err(format!(
+                            "API validation failed: {} - {}",
+                            status, error_text
+                        ))
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to connect to Gemini API for validation: {}", e);
+                    Err(format!("Connection failed: {}", e))
+                }
+            }
+        }
         _ => {
             error!("Unknown provider for validation: {}", provider);
             Err(format!("Unsupported provider: {}", provider))
@@ -573,115 +628,15 @@ async fn validate_api_key(provider: String, api_key: String) -> Result<bool, Str
 }
 
 #[tauri::command]
-async fn get_claude_models(_app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
+async fn get_claude_models(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
     info!("Fetching Claude models from API (backend)");
-
-    // Get Claude API key from storage
-    let api_key = match get_api_key_file("claude") {
-        Ok(Some(key)) => key,
-        Ok(None) => {
-            info!("No Claude API key found");
-            return Ok(vec![]);
-        }
-        Err(e) => {
-            error!("Error getting Claude API key: {}", e);
-            return Ok(vec![]);
-        }
-    };
-
-    let client = reqwest::Client::new();
-    
-    let response = client
-        .get("https://api.anthropic.com/v1/models?limit=20")
-        .header("x-api-key", &api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .send()
-        .await;
-
-    match response {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(data) => {
-                        if let Some(models_array) = data.get("data").and_then(|d| d.as_array()) {
-                            let models: Vec<serde_json::Value> = models_array
-                                .iter()
-                                .map(|model| {
-                                    serde_json::json!({
-                                        "id": model.get("id").and_then(|v| v.as_str()).unwrap_or("unknown"),
-                                        "name": model.get("display_name").and_then(|v| v.as_str())
-                                            .unwrap_or(model.get("id").and_then(|v| v.as_str()).unwrap_or("unknown")),
-                                        "description": "Claude API model",
-                                        "provider": "claude"
-                                    })
-                                })
-                                .collect();
-                            info!("Successfully fetched {} Claude models", models.len());
-                            Ok(models)
-                        } else {
-                            error!("Claude API response missing 'data' field");
-                            Ok(vec![])
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to parse Claude models response: {}", e);
-                        Ok(vec![])
-                    }
-                }
-            } else {
-                let status = resp.status();
-                let error_text = resp.text().await.unwrap_or_default();
-                error!("Claude models API error {}: {}", status, error_text);
-                Ok(vec![])
-            }
-        }
-        Err(e) => {
-            error!("Failed to connect to Claude API: {}", e);
-            Ok(vec![])
-        }
-    }
+    ClaudeProvider::new(app).list_models().await
 }
 
 #[tauri::command]
-async fn get_perplexity_models() -> Result<Vec<serde_json::Value>, String> {
+async fn get_perplexity_models(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
     info!("Getting available Perplexity models");
-
-    // Return available Perplexity models
-    let models = vec![
-        serde_json::json!({
-            "id": "sonar-deep-research",
-            "name": "Sonar Deep Research",
-            "description": "Deep research with comprehensive analysis",
-            "provider": "perplexity"
-        }),
-        serde_json::json!({
-            "id": "sonar-reasoning-pro",
-            "name": "Sonar Reasoning Pro",
-            "description": "Advanced reasoning capabilities",
-            "provider": "perplexity"
-        }),
-        serde_json::json!({
-            "id": "sonar-reasoning",
-            "name": "Sonar Reasoning",
-            "description": "Core reasoning model",
-            "provider": "perplexity"
-        }),
-        serde_json::json!({
-            "id": "sonar-pro",
-            "name": "Sonar Pro",
-            "description": "Professional grade search and chat",
-            "provider": "perplexity"
-        }),
-        serde_json::json!({
-            "id": "sonar",
-            "name": "Sonar",
-            "description": "Standard search and chat model",
-            "provider": "perplexity"
-        }),
-    ];
-
-    Ok(models)
+    PerplexityProvider::new(app).list_models().await
 }
 
 #[tauri::command]
@@ -695,7 +650,7 @@ async fn debug_api_keys(_app: tauri::AppHandle) -> Result<String, String> {
     let service = "olly";
 
     // Check keyring for known providers
-    for provider in ["claude", "perplexity", "openai"] {
+    for provider in ["claude", "perplexity", "openai", "gemini"] {
         let username = format!("{}_api_key", provider);
         info!("Checking keyring for {}: service='{}'", provider, service);
 
@@ -924,16 +879,27 @@ async fn test_exact_keyring() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn get_all_models() -> Result<Vec<serde_json::Value>, String> {
+async fn get_all_models(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
     info!("Getting all available models from all providers");
 
     let mut all_models = Vec::new();
 
     // Claude models are now handled dynamically in the frontend
 
-    // Add Perplexity models
-    let perplexity_models = get_perplexity_models().await?;
-    all_models.extend(perplexity_models);
+    // Perplexity, OpenAI and Gemini (empty when no key is stored) and the
+    // user-defined OpenAI-compatible endpoints, fetched concurrently
+    let openai = OpenAiProvider::openai();
+    let gemini = GeminiProvider::new();
+    let (perplexity_models, openai_models, gemini_models, endpoint_models) = tokio::join!(
+        get_perplexity_models(app),
+        openai.list_models(),
+        gemini.list_models(),
+        endpoints::list_all_models()
+    );
+    all_models.extend(perplexity_models?);
+    all_models.extend(openai_models?);
+    all_models.extend(gemini_models?);
+    all_models.extend(endpoint_models);
 
     // Add Fal model
     all_models.push(serde_json::json!({
@@ -950,72 +916,9 @@ async fn get_all_models() -> Result<Vec<serde_json::Value>, String> {
 }
 
 #[tauri::command]
-async fn get_ollama_models() -> Result<Vec<serde_json::Value>, String> {
-    info!("Getting Ollama models from localhost:11434");
-
-    let client = reqwest::Client::new();
-
-    match client.get("http://localhost:11434/api/tags").send().await {
-        Ok(response) => {
-            match response.json::<serde_json::Value>().await {
-                Ok(data) => {
-                    if let Some(models) = data.get("models").and_then(|m| m.as_array()) {
-                        let ollama_models = models.iter().filter_map(|model| {
-                            let name = model.get("name").and_then(|n| n.as_str())?;
-                            let modified_at = model.get("modified_at").and_then(|m| m.as_str()).unwrap_or("Unknown");
-                            let details = model.get("details").cloned().unwrap_or_else(|| serde_json::json!({}));
-                            
-                            // Format the date to human-readable format
-                            let formatted_date = if modified_at != "Unknown" {
-                                match DateTime::parse_from_rfc3339(modified_at) {
-                                    Ok(dt) => {
-                                        // Convert to local time and format
-                                        let local_dt = dt.with_timezone(&chrono::Local);
-                                        local_dt.format("%b %d, %Y - %I:%M %p %Z").to_string()
-                                    }
-                                    Err(_) => modified_at.to_string()
-                                }
-                            } else {
-                                "Unknown".to_string()
-                            };
-                            
-                            Some(serde_json::json!({
-                                "id": name,
-                                "name": name,
-                                "description": format!("{} - {}", 
-                                    details.get("parameter_size").and_then(|p| p.as_str()).unwrap_or("Unknown"),
-                                    formatted_date
-                                ),
-                                "provider": "ollama",
-                                "details": {
-                                    "modified_at": modified_at,
-                                    "parameter_size": details.get("parameter_size").and_then(|p| p.as_str()).unwrap_or("Unknown"),
-                                    "quantization_level": details.get("quantization_level").and_then(|q| q.as_str()).unwrap_or("Unknown")
-                                }
-                            }))
-                        }).collect::<Vec<_>>();
-
-                        info!("Successfully fetched {} Ollama models", ollama_models.len());
-                        Ok(ollama_models)
-                    } else {
-                        info!("No models found in Ollama response");
-                        Ok(Vec::new())
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to parse Ollama response: {}", e);
-                    Err(format!("Failed to parse Ollama response: {}", e))
-                }
-            }
-        }
-        Err(e) => {
-            error!("Failed to connect to Ollama: {}", e);
-            Err(format!(
-                "Failed to connect to Ollama at localhost:11434: {}",
-                e
-            ))
-        }
-    }
+async fn get_ollama_models(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
+    info!("Getting Ollama models from the configured endpoint");
+    OllamaProvider::new(app).list_models().await
 }
 
 fn main() {
@@ -1039,6 +942,8 @@ fn main() {
     dotenvy::dotenv().ok();
 
     tauri::Builder::default()
+        .manage(StreamRegistry::default())
+        .manage(ProfileStore::default())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_shell::init())
@@ -1046,14 +951,42 @@ fn main() {
         .plugin(tauri_plugin_calendar::init())
         .invoke_handler(tauri::generate_handler![
             greet,
+            providers::chat,
+            providers::stream_chat,
+            providers::cancel_stream,
+            providers::get_chat_providers,
             ask_claude,
             stream_claude,
             ask_perplexity,
             stream_perplexity,
+            stream_ollama,
+            abort_ollama,
             get_perplexity_models,
             get_claude_models,
             get_all_models,
             get_ollama_models,
+            ollama::get_ollama_settings,
+            ollama::set_ollama_settings,
+            ollama::get_ollama_client_config,
+            ollama::test_ollama_connection,
+            ollama::models::pull_ollama_model,
+            ollama::models::create_ollama_model,
+            ollama::models::build_ollama_modelfile,
+            ollama::models::delete_ollama_model,
+            ollama::models::copy_ollama_model,
+            ollama::models::show_ollama_model,
+            ollama::models::list_running_ollama_models,
+            ollama::models::load_ollama_model,
+            ollama::models::unload_ollama_model,
+            ollama::models::set_ollama_keep_alive,
+            endpoints::get_openai_endpoints,
+            endpoints::save_openai_endpoint,
+            endpoints::delete_openai_endpoint,
+            endpoints::get_openai_endpoint_models,
+            usage::get_usage_report,
+            profiles::get_model_profile,
+            profiles::set_model_profile,
+            documents::pick_document,
             get_env,
             store_api_key,
             get_api_key,
@@ -1213,122 +1146,6 @@ async fn auto_migrate_keys(app: &tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-// Claude API
-
-use futures_util::stream::StreamExt;
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize)]
-struct ClaudeRequest {
-    model: String,
-    messages: Vec<Message>,
-    max_tokens: u32,
-    temperature: f32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    stream: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tools: Option<Vec<Tool>>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]
-enum MessageContent {
-    Text(String),
-    Multimodal(Vec<ContentBlock>),
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(tag = "type")]
-enum ContentBlock {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "image")]
-    Image { source: ImageSource },
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct ImageSource {
-    #[serde(rename = "type")]
-    source_type: String,
-    media_type: String,
-    data: String,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct Message {
-    role: String,
-    content: MessageContent,
-}
-
-#[derive(Serialize)]
-struct Tool {
-    #[serde(rename = "type")]
-    tool_type: String,
-    name: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    max_uses: Option<u32>,
-}
-
-#[derive(Deserialize, Debug)]
-struct ClaudeResponse {
-    content: Vec<Content>,
-}
-
-#[derive(Deserialize, Debug)]
-struct Content {
-    text: String,
-}
-
-// Streaming response structures for Claude
-#[derive(Deserialize, Debug)]
-#[serde(tag = "type")]
-enum ClaudeStreamEvent {
-    #[serde(rename = "message_start")]
-    MessageStart { message: serde_json::Value },
-    #[serde(rename = "content_block_start")]
-    ContentBlockStart {
-        index: u32,
-        content_block: serde_json::Value,
-    },
-    #[serde(rename = "content_block_delta")]
-    ContentBlockDelta {
-        index: u32,
-        delta: ClaudeStreamDelta,
-    },
-    #[serde(rename = "content_block_stop")]
-    ContentBlockStop { index: u32 },
-    #[serde(rename = "message_delta")]
-    MessageDelta {
-        delta: serde_json::Value,
-        usage: serde_json::Value,
-    },
-    #[serde(rename = "message_stop")]
-    MessageStop,
-    #[serde(rename = "ping")]
-    Ping,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(tag = "type")]
-enum ClaudeStreamDelta {
-    #[serde(rename = "text_delta")]
-    TextDelta { text: String },
-    #[serde(rename = "citations_delta")]
-    CitationsDelta { citation: Citation },
-    #[serde(other)]
-    Other,
-}
-
-#[derive(Deserialize, Debug)]
-struct Citation {
-    #[serde(rename = "type")]
-    citation_type: String,
-    cited_text: String,
-    url: String,
-    title: String,
-    encrypted_index: String,
-}
-
 fn get_config_path() -> PathBuf {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
     PathBuf::from(home).join(".olly").join("config.env")
@@ -1451,7 +1268,7 @@ fn simple_decode(input: &[u8]) -> String {
     String::from_utf8(decoded).unwrap_or_default()
 }
 
-fn load_api_key(_app: &tauri::AppHandle, provider: &str) -> Result<String, String> {
+fn load_api_key(provider: &str) -> Result<String, String> {
     info!("Loading API key for provider: {}", provider);
 
     // Log the keys directory path for debugging
@@ -1619,537 +1436,83 @@ async fn migrate_claude_key(_app: tauri::AppHandle) -> Result<String, String> {
 #[tauri::command]
 async fn ask_claude(
     app: tauri::AppHandle,
+    profiles: tauri::State<'_, ProfileStore>,
     model: String,
     prompt: String,
     messages: Vec<Message>,
-) -> Result<String, String> {
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<ChatResponse, String> {
     info!("Starting ask_claude with prompt: {}", prompt);
 
-    let client = reqwest::Client::new();
-
-    // Load API key from secure storage, environment, or config file
-    let api_key = load_api_key(&app, "claude")?;
-    info!("Successfully loaded API key");
-
-    let model_name = model;
-    info!("Using Claude model: {}", model_name);
-
-    let request = ClaudeRequest {
-        model: model_name,
-        messages: if messages.is_empty() {
-            vec![Message {
-                role: "user".to_string(),
-                content: MessageContent::Text(prompt),
-            }]
-        } else {
-            messages
-        },
-        max_tokens: 1024,
-        temperature: 0.0,
-        stream: None,
-        tools: Some(vec![Tool {
-            tool_type: "web_search_20250305".to_string(),
-            name: "web_search".to_string(),
-            max_uses: Some(5),
-        }]),
-    };
-
-    info!("Sending request to Claude API...");
-    let response = match client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", &api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .json(&request)
-        .send()
-        .await
-    {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let error_text = resp
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Could not read error response".to_string());
-                error!(
-                    "Claude API request failed with status {}: {}",
-                    status, error_text
-                );
-
-                if status == 401 {
-                    return Err(
-                        "Authentication failed. Please check your Claude API key in Settings."
-                            .to_string(),
-                    );
-                } else if status == 429 {
-                    return Err("Rate limit exceeded. Please try again later.".to_string());
-                } else {
-                    return Err(format!("Claude API error ({}): {}", status, error_text));
-                }
-            }
-            info!(
-                "Received response from Claude API with status: {}",
-                resp.status()
-            );
-            resp
-        }
-        Err(e) => {
-            error!("Failed to connect to Claude API: {}", e);
-            if e.is_timeout() {
-                return Err("Request timed out. Please check your internet connection.".to_string());
-            } else if e.is_connect() {
-                return Err(
-                    "Could not connect to Claude API. Please check your internet connection."
-                        .to_string(),
-                );
-            } else {
-                return Err(format!("Network error: {}", e));
-            }
-        }
-    };
-
-    let claude_response: ClaudeResponse = match response.json::<ClaudeResponse>().await {
-        Ok(resp) => {
-            info!(
-                "Successfully parsed response: {:?}",
-                resp.content.first().map(|c| &c.text)
-            );
-            resp
-        }
-        Err(e) => {
-            error!("Failed to parse Claude API response: {}", e);
-            return Err(format!("Failed to parse response: {}", e));
-        }
-    };
-
-    if claude_response.content.is_empty() {
-        error!("Claude response content array is empty");
-        return Err("Empty response from Claude API".to_string());
-    }
+    // Includes the web search citations alongside the text
+    let request = ChatRequest::new("claude", model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    let response = ClaudeProvider::new(app.clone()).complete(&request).await?;
 
     info!("Returning response from Claude");
-    Ok(claude_response.content[0].text.clone())
+    Ok(response)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn stream_claude(
-    window: tauri::Window,
     app: tauri::AppHandle,
+    streams: tauri::State<'_, StreamRegistry>,
+    profiles: tauri::State<'_, ProfileStore>,
+    on_event: Channel<StreamEvent>,
     model: String,
     prompt: String,
     messages: Vec<Message>,
-) -> Result<(), String> {
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<u64, String> {
     info!("Starting stream_claude with prompt: {}", prompt);
 
-    let client = reqwest::Client::new();
-
-    // Load API key from secure storage, environment, or config file
-    let api_key = load_api_key(&app, "claude")?;
-    info!("Successfully loaded API key");
-
-    let model_name = model;
-    info!("Using Claude model for streaming: {}", model_name);
-
-    let request = ClaudeRequest {
-        model: model_name,
-        messages: if messages.is_empty() {
-            vec![Message {
-                role: "user".to_string(),
-                content: MessageContent::Text(prompt),
-            }]
-        } else {
-            messages
-        },
-        max_tokens: 1024,
-        temperature: 0.0,
-        stream: Some(true),
-        tools: Some(vec![Tool {
-            tool_type: "web_search_20250305".to_string(),
-            name: "web_search".to_string(),
-            max_uses: Some(5),
-        }]),
-    };
-
-    info!("Sending streaming request to Claude API...");
-    let response = match client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .json(&request)
-        .send()
+    let request = ChatRequest::new("claude", model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    streams
+        .run(
+            &ClaudeProvider::new(app.clone()),
+            &request,
+            &StreamSink::new(on_event),
+        )
         .await
-    {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let error_text = resp
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Could not read error response".to_string());
-                error!("API request failed with status {}: {}", status, error_text);
-                return Err(format!("API request failed: {} - {}", status, error_text));
-            }
-            info!(
-                "Received streaming response from Claude API with status: {}",
-                resp.status()
-            );
-            resp
-        }
-        Err(e) => {
-            error!("Failed to send request to Claude API: {}", e);
-            return Err(e.to_string());
-        }
-    };
-
-    let mut stream = response.bytes_stream();
-    let mut full_response = String::new();
-    let mut buffer = String::new();
-
-    while let Some(item) = stream.next().await {
-        match item {
-            Ok(bytes) => {
-                let chunk = String::from_utf8_lossy(&bytes);
-                info!("Received chunk from Claude: {}", chunk);
-
-                // Add chunk to buffer to handle split JSON objects
-                buffer.push_str(&chunk);
-
-                // Process complete lines from buffer
-                while let Some(line_end) = buffer.find('\n') {
-                    let line = buffer[..line_end].to_string();
-                    buffer = buffer[line_end + 1..].to_string();
-
-                    if line.is_empty() || line == "data: [DONE]" {
-                        continue;
-                    }
-
-                    // Remove the "data: " prefix if present
-                    let json_str = if line.starts_with("data: ") {
-                        &line[6..]
-                    } else {
-                        &line
-                    };
-
-                    // Skip if line is an event message or empty/invalid JSON
-                    if line.starts_with("event:") {
-                        info!("Received Claude event: {}", line);
-                        continue;
-                    }
-
-                    // Skip if the JSON string is empty or obviously invalid
-                    if json_str.trim().is_empty() || !json_str.trim().starts_with('{') {
-                        error!("Skipping invalid JSON from Claude: {}", json_str);
-                        continue;
-                    }
-
-                    // Parse the JSON
-                    match serde_json::from_str::<ClaudeStreamEvent>(json_str) {
-                        Ok(event) => {
-                            match event {
-                                ClaudeStreamEvent::ContentBlockDelta { delta, .. } => {
-                                    match delta {
-                                        ClaudeStreamDelta::TextDelta { text } => {
-                                            if !text.is_empty() {
-                                                info!("Parsed text from Claude delta: {}", text);
-                                                full_response.push_str(&text);
-
-                                                // Emit event to frontend
-                                                if let Err(e) = window.emit("claude-stream", &text)
-                                                {
-                                                    error!(
-                                                        "Failed to emit claude-stream event: {}",
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        }
-                                        ClaudeStreamDelta::CitationsDelta { citation } => {
-                                            info!(
-                                                "Received citation: {} - {}",
-                                                citation.title, citation.url
-                                            );
-                                            // Could emit citation event to frontend if needed
-                                        }
-                                        ClaudeStreamDelta::Other => {
-                                            info!("Received other delta type, ignoring");
-                                        }
-                                    }
-                                }
-                                ClaudeStreamEvent::MessageStart { .. } => {
-                                    info!("Claude message started");
-                                }
-                                ClaudeStreamEvent::ContentBlockStart { .. } => {
-                                    info!("Claude content block started");
-                                }
-                                ClaudeStreamEvent::ContentBlockStop { .. } => {
-                                    info!("Claude content block stopped");
-                                }
-                                ClaudeStreamEvent::MessageStop => {
-                                    info!("Claude message stopped");
-                                }
-                                ClaudeStreamEvent::MessageDelta { .. } => {
-                                    info!("Claude message delta received");
-                                }
-                                ClaudeStreamEvent::Ping => {
-                                    info!("Claude ping received");
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to parse JSON from Claude chunk: {} - Error: {}",
-                                json_str, e
-                            );
-                            // Try to salvage any content by looking for text pattern
-                            if let Some(content_start) = json_str.find("\"text\": \"") {
-                                if let Some(content_end) = json_str[content_start + 9..].find("\"")
-                                {
-                                    let content = &json_str
-                                        [content_start + 9..content_start + 9 + content_end];
-                                    info!("Salvaged content from Claude: {}", content);
-                                    full_response.push_str(content);
-
-                                    // Emit event to frontend with salvaged content
-                                    if let Err(e) = window.emit("claude-stream", content) {
-                                        error!("Failed to emit claude-stream event with salvaged content: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Error reading from Claude stream: {}", e);
-                return Err(format!("Error reading from stream: {}", e));
-            }
-        }
-    }
-
-    // Process any remaining content in buffer
-    if !buffer.trim().is_empty() {
-        info!("Processing remaining buffer content: {}", buffer);
-
-        for line in buffer.lines() {
-            if line.is_empty() || line == "data: [DONE]" {
-                continue;
-            }
-
-            let json_str = if line.starts_with("data: ") {
-                &line[6..]
-            } else {
-                line
-            };
-
-            if line.starts_with("event:")
-                || json_str.trim().is_empty()
-                || !json_str.trim().starts_with('{')
-            {
-                continue;
-            }
-
-            match serde_json::from_str::<ClaudeStreamEvent>(json_str) {
-                Ok(event) => {
-                    if let ClaudeStreamEvent::ContentBlockDelta { delta, .. } = event {
-                        match delta {
-                            ClaudeStreamDelta::TextDelta { text } => {
-                                if !text.is_empty() {
-                                    full_response.push_str(&text);
-                                    if let Err(e) = window.emit("claude-stream", &text) {
-                                        error!(
-                                            "Failed to emit claude-stream event from buffer: {}",
-                                            e
-                                        );
-                                    }
-                                }
-                            }
-                            ClaudeStreamDelta::CitationsDelta { citation } => {
-                                info!(
-                                    "Received citation from buffer: {} - {}",
-                                    citation.title, citation.url
-                                );
-                            }
-                            ClaudeStreamDelta::Other => {
-                                info!("Received other delta type from buffer, ignoring");
-                            }
-                        }
-                    }
-                }
-                Err(_) => {
-                    // Try salvage operation for remaining buffer
-                    if let Some(content_start) = json_str.find("\"text\": \"") {
-                        if let Some(content_end) = json_str[content_start + 9..].find("\"") {
-                            let content =
-                                &json_str[content_start + 9..content_start + 9 + content_end];
-                            full_response.push_str(content);
-                            if let Err(e) = window.emit("claude-stream", content) {
-                                error!("Failed to emit salvaged content from buffer: {}", e);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    info!(
-        "Streaming completed from Claude. Full response: {}",
-        full_response
-    );
-
-    // Emit completion event with the full response
-    if let Err(e) = window.emit("claude-stream-done", full_response) {
-        error!("Failed to emit claude-stream-done event: {}", e);
-    }
-
-    Ok(())
-}
-
-// Perplexity API
-
-#[derive(Deserialize)]
-struct PerplexityResponse {
-    choices: Vec<PerplexityChoice>,
-}
-
-#[derive(Deserialize)]
-struct PerplexityChoice {
-    message: PerplexityMessage,
-}
-
-#[derive(Deserialize)]
-struct PerplexityMessage {
-    content: String,
-}
-
-// Streaming response structures
-#[derive(Deserialize, Debug)]
-struct PerplexityStreamResponse {
-    choices: Vec<PerplexityStreamChoice>,
-    citations: Option<Vec<String>>,
-}
-
-#[derive(Deserialize, Debug)]
-struct PerplexityStreamChoice {
-    delta: PerplexityStreamDelta,
-}
-
-#[derive(Deserialize, Debug)]
-struct PerplexityStreamDelta {
-    content: Option<String>,
 }
 
 #[tauri::command]
 async fn ask_perplexity(
     app: tauri::AppHandle,
+    profiles: tauri::State<'_, ProfileStore>,
     model: String,
     prompt: String,
-) -> Result<String, String> {
+    messages: Vec<Message>,
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<ChatResponse, String> {
     info!(
         "Starting ask_perplexity with model: {} and prompt: {}",
         model, prompt
     );
 
-    let client = reqwest::Client::new();
-
-    // Load API key from secure storage
-    let api_key = match get_api_key(app.clone(), "perplexity".to_string()).await? {
-        Some(key) => key,
-        None => return Err("Perplexity API key not found. Please add it in Settings.".to_string()),
-    };
-
-    // Build request body with the specified model
-    let request_body = serde_json::json!({
-        "model": model,
-        "messages": [
-            {
-                "role": "user",
-                "content": prompt
-            }
-        ],
-        "max_tokens": 1024,
-        "temperature": 0.7
-    });
-
-    info!("Sending request to Perplexity API...");
-    let response = match client
-        .post("https://api.perplexity.ai/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-    {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let error_text = resp
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Could not read error response".to_string());
-                error!(
-                    "Perplexity API request failed with status {}: {}",
-                    status, error_text
-                );
-
-                if status == 401 {
-                    return Err(
-                        "Authentication failed. Please check your Perplexity API key in Settings."
-                            .to_string(),
-                    );
-                } else if status == 429 {
-                    return Err("Rate limit exceeded. Please try again later.".to_string());
-                } else {
-                    return Err(format!("Perplexity API error ({}): {}", status, error_text));
-                }
-            }
-            info!(
-                "Received response from Perplexity API with status: {}",
-                resp.status()
-            );
-            resp
-        }
-        Err(e) => {
-            error!("Failed to connect to Perplexity API: {}", e);
-            if e.is_timeout() {
-                return Err("Request timed out. Please check your internet connection.".to_string());
-            } else if e.is_connect() {
-                return Err(
-                    "Could not connect to Perplexity API. Please check your internet connection."
-                        .to_string(),
-                );
-            } else {
-                return Err(format!("Network error: {}", e));
-            }
-        }
-    };
-
-    let perplexity_response: PerplexityResponse = match response.json::<PerplexityResponse>().await
-    {
-        Ok(resp) => {
-            info!("Successfully parsed Perplexity response");
-            resp
-        }
-        Err(e) => {
-            error!("Failed to parse Perplexity API response: {}", e);
-            return Err(format!("Failed to parse response: {}", e));
-        }
-    };
-
-    if perplexity_response.choices.is_empty() {
-        error!("Perplexity response choices array is empty");
-        return Err("Empty response from Perplexity API".to_string());
-    }
+    let request = ChatRequest::new("perplexity", model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    let response = PerplexityProvider::new(app.clone())
+        .complete(&request)
+        .await?;
 
     info!("Returning response from Perplexity");
-    Ok(perplexity_response.choices[0].message.content.clone())
+    Ok(response)
 }
 
 // Calendar summarization command
 #[tauri::command]
 async fn summarize_calendar_events(
-    _app: tauri::AppHandle,
+    app: tauri::AppHandle,
+    profiles: tauri::State<'_, ProfileStore>,
     events_json: String,
     model: Option<String>,
 ) -> Result<String, String> {
@@ -2198,193 +1561,82 @@ async fn summarize_calendar_events(
     // Use local Ollama model for summarization (or user's preferred model)
     let model_name = model.unwrap_or_else(|| "gemma3:1b".to_string());
 
-    // Call local Ollama model
-    let client = reqwest::Client::new();
-    let request_body = serde_json::json!({
-        "model": model_name,
-        "prompt": prompt,
-        "stream": false,
-        "options": {
-            "temperature": 0.3,
-            "num_predict": 300
-        }
-    });
-
-    info!("Sending request to Ollama with model: {}", model_name);
-
-    let response = client
-        .post("http://localhost:11434/api/generate")
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to call Ollama API: {}. Is Ollama running?", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Ollama API error: {}", error_text));
-    }
+    // The model's profile wins over the defaults for short summaries
+    let mut request =
+        ChatRequest::new("ollama", model_name, prompt, Vec::new()).with_options(None, &profiles);
+    request.options.temperature.get_or_insert(0.3);
+    request.options.max_tokens.get_or_insert(300);
 
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+    info!("Sending request to Ollama with model: {}", request.model);
 
-    let summary = response_json
-        .get("response")
-        .and_then(|v| v.as_str())
-        .ok_or("No response from Ollama")?
-        .to_string();
+    let summary = OllamaProvider::new(app).complete(&request).await?.content;
 
     info!("Successfully generated calendar summary");
     Ok(summary)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn stream_perplexity(
-    window: tauri::Window,
     app: tauri::AppHandle,
+    streams: tauri::State<'_, StreamRegistry>,
+    profiles: tauri::State<'_, ProfileStore>,
+    on_event: Channel<StreamEvent>,
     model: String,
     prompt: String,
-) -> Result<(), String> {
+    messages: Vec<Message>,
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<u64, String> {
     info!(
         "Starting stream_perplexity with model: {} and prompt: {}",
         model, prompt
     );
 
-    let client = reqwest::Client::new();
-
-    // Load API key from secure storage
-    let api_key = match get_api_key(app.clone(), "perplexity".to_string()).await? {
-        Some(key) => key,
-        None => return Err("Perplexity API key not found. Please add it in Settings.".to_string()),
-    };
-
-    // Build request body with stream enabled
-    let request_body = serde_json::json!({
-        "model": model,
-        "messages": [
-            {
-                "role": "user",
-                "content": prompt
-            }
-        ],
-        "max_tokens": 1024,
-        "temperature": 0.7,
-        "stream": true
-    });
-
-    info!("Sending streaming request to Perplexity API");
-
-    let response = match client
-        .post("https://api.perplexity.ai/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
+    // The completion message carries the citations
+    let request = ChatRequest::new("perplexity", model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    streams
+        .run(
+            &PerplexityProvider::new(app.clone()),
+            &request,
+            &StreamSink::new(on_event),
+        )
         .await
-    {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let error_text = resp
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Could not read error response".to_string());
-                error!("API request failed with status {}: {}", status, error_text);
-                return Err(format!("API request failed: {} - {}", status, error_text));
-            }
-            info!(
-                "Received streaming response from Perplexity API with status: {}",
-                resp.status()
-            );
-            resp
-        }
-        Err(e) => {
-            error!("Failed to send request to Perplexity API: {}", e);
-            return Err(e.to_string());
-        }
-    };
-
-    let mut stream = response.bytes_stream();
-    let mut full_response = String::new();
-    let mut buffer = String::new();
-    let mut citations: Option<Vec<String>> = None;
-
-    while let Some(item) = stream.next().await {
-        match item {
-            Ok(bytes) => {
-                let chunk = String::from_utf8_lossy(&bytes);
-                buffer.push_str(&chunk);
-
-                // Process complete lines only
-                while let Some(newline_pos) = buffer.find('\n') {
-                    let line = buffer[..newline_pos].to_string();
-                    buffer = buffer[newline_pos + 1..].to_string();
-
-                    if line.trim().is_empty() || line.trim() == "data: [DONE]" {
-                        continue;
-                    }
-
-                    // Remove the "data: " prefix if present
-                    let json_str = if line.starts_with("data: ") {
-                        &line[6..]
-                    } else {
-                        &line
-                    };
-
-                    // Skip if the JSON string is empty or obviously invalid
-                    if json_str.trim().is_empty() || !json_str.trim().starts_with('{') {
-                        continue;
-                    }
-
-                    // Parse the JSON
-                    match serde_json::from_str::<PerplexityStreamResponse>(json_str) {
-                        Ok(parsed) => {
-                            // Capture citations if present (they come in the final chunk)
-                            if let Some(ref cites) = parsed.citations {
-                                citations = Some(cites.clone());
-                                info!("Received {} citations", cites.len());
-                            }
-
-                            // Extract content from the first choice's delta if available
-                            if let Some(choice) = parsed.choices.first() {
-                                if let Some(content) = &choice.delta.content {
-                                    full_response.push_str(content);
-
-                                    // Emit event to frontend
-                                    if let Err(e) = window.emit("perplexity-stream", content) {
-                                        error!("Failed to emit perplexity-stream event: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to parse JSON from line: {} - Error: {}",
-                                json_str, e
-                            );
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Error reading from stream: {}", e);
-                return Err(format!("Error reading from stream: {}", e));
-            }
-        }
-    }
-
-    info!("Streaming completed. Full response: {}", full_response);
-
-    // Emit completion event with the full response and citations
-    let completion_data = serde_json::json!({
-        "content": full_response,
-        "citations": citations
-    });
+}
 
-    if let Err(e) = window.emit("perplexity-stream-done", completion_data) {
-        error!("Failed to emit perplexity-stream-done event: {}", e);
-    }
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn stream_ollama(
+    app: tauri::AppHandle,
+    streams: tauri::State<'_, StreamRegistry>,
+    profiles: tauri::State<'_, ProfileStore>,
+    on_event: Channel<StreamEvent>,
+    model: String,
+    prompt: String,
+    messages: Vec<Message>,
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<u64, String> {
+    info!("Starting stream_ollama with model: {}", model);
+
+    let request = ChatRequest::new("ollama", model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    streams
+        .run(
+            &OllamaProvider::new(app.clone()),
+            &request,
+            &StreamSink::new(on_event),
+        )
+        .await
+}
 
+/// Stops every in-flight Ollama stream; `cancel_stream` stops a single one.
+#[tauri::command]
+async fn abort_ollama(streams: tauri::State<'_, StreamRegistry>) -> Result<(), String> {
+    let cancelled = streams.cancel_provider("ollama");
+    info!("Aborted {} Ollama stream(s)", cancelled);
     Ok(())
 }
@@ -0,0 +1,445 @@
+// Ollama endpoint settings
+//
+// The endpoint (URL plus optional auth for a reverse proxy) is persisted in
+// ~/.olly/ollama.json and used by every backend Ollama call. Credentials are
+// kept out of that file and go through the regular key storage instead.
+// Per-model keep-alive pins live there too: Ollama resets a model's expiry
+// on every request, so each request has to repeat the pinned value.
+
+use base64::Engine;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+pub mod models;
+
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_PORT: u16 = 11434;
+
+// Key storage slot for the bearer token / basic auth password
+const AUTH_KEY_PROVIDER: &str = "ollama_auth";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaAuthType {
+    #[default]
+    None,
+    Bearer,
+    Basic,
+}
+
+/// How long Ollama keeps a model in memory after a request: seconds, or a
+/// duration such as "10m" or "24h". Negative keeps it loaded indefinitely,
+/// zero unloads it right away.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum KeepAlive {
+    Seconds(i64),
+    Duration(String),
+}
+
+impl KeepAlive {
+    /// Checks a duration against the Go syntax Ollama parses, e.g. "10m",
+    /// "1h30m" or "-1s". Ollama rejects every request carrying a bad one.
+    pub fn validate(&self) -> Result<(), String> {
+        let KeepAlive::Duration(duration) = self else {
+            return Ok(());
+        };
+        if is_go_duration(duration) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid keep-alive '{}', expected a duration such as 10m or 1h30m",
+                duration
+            ))
+        }
+    }
+}
+
+/// Whether `text` is a Go `time.ParseDuration` string: an optional sign, then
+/// numbers each followed by a unit, or a bare zero.
+fn is_go_duration(text: &str) -> bool {
+    let rest = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if rest == "0" {
+        return true;
+    }
+
+    let mut rest = rest;
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number = &rest[..number_len];
+        if number.is_empty() || number == "." || number.matches('.').count() > 1 {
+            return false;
+        }
+        rest = &rest[number_len..];
+
+        // Longest units first, so "ms" isn't read as "m"
+        let Some(unit) = ["ns", "us", "µs", "ms", "h", "m", "s"]
+            .iter()
+            .find(|unit| rest.starts_with(*unit))
+        else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OllamaSettings {
+    pub url: String,
+    #[serde(default)]
+    pub auth_type: OllamaAuthType,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Keep-alive pinned per model, sent with every request for it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keep_alive: BTreeMap<String, KeepAlive>,
+}
+
+impl Default for OllamaSettings {
+    fn default() -> Self {
+        // Respect the standard OLLAMA_HOST variable when nothing is saved
+        let url = std::env::var("OLLAMA_HOST")
+            .ok()
+            .and_then(|host| normalize_url(&host).ok())
+            .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string());
+
+        Self {
+            url,
+            auth_type: OllamaAuthType::None,
+            username: None,
+            keep_alive: BTreeMap::new(),
+        }
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".olly").join("ollama.json")
+}
+
+/// Adds a scheme when missing (`OLLAMA_HOST` is often just `host:port`) and
+/// strips trailing slashes so paths can be appended directly. Like Ollama's
+/// own client, a bare host gets port 11434; with a scheme the scheme's
+/// default port applies.
+fn normalize_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    if url.is_empty() {
+        return Err("Ollama URL cannot be empty".to_string());
+    }
+
+    let has_scheme = url.contains("://");
+    let url = if has_scheme {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    };
+
+    let mut parsed = reqwest::Url::parse(&url).map_err(|e| format!("Invalid Ollama URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!(
+            "Invalid Ollama URL scheme '{}', expected http or https",
+            parsed.scheme()
+        ));
+    }
+    if has_scheme || parsed.port().is_some() {
+        return Ok(url);
+    }
+
+    parsed
+        .set_port(Some(DEFAULT_OLLAMA_PORT))
+        .map_err(|_| format!("Invalid Ollama URL: {}", url))?;
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+impl OllamaSettings {
+    pub fn load() -> Self {
+        let path = get_settings_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    error!("Failed to parse Ollama settings, using defaults: {}", e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_settings_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let data = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize Ollama settings: {}", e))?;
+        fs::write(&path, data).map_err(|e| format!("Failed to write Ollama settings: {}", e))?;
+
+        info!("Saved Ollama settings ({})", self.url);
+        Ok(())
+    }
+
+    /// Value for the `Authorization` header, if this endpoint needs one.
+    fn auth_header(&self) -> Option<String> {
+        if self.auth_type == OllamaAuthType::None {
+            return None;
+        }
+
+        let secret = match crate::get_api_key_file(AUTH_KEY_PROVIDER) {
+            Ok(Some(secret)) => secret,
+            Ok(None) => {
+                error!("Ollama auth is enabled but no credentials are stored");
+                return None;
+            }
+            Err(e) => {
+                error!("Failed to read Ollama credentials: {}", e);
+                return None;
+            }
+        };
+
+        match self.auth_type {
+            OllamaAuthType::Bearer => Some(format!("Bearer {}", secret)),
+            OllamaAuthType::Basic => {
+                let username = self.username.clone().unwrap_or_default();
+                let encoded = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, secret));
+                Some(format!("Basic {}", encoded))
+            }
+            OllamaAuthType::None => None,
+        }
+    }
+}
+
+/// HTTP client bound to the configured Ollama endpoint.
+pub struct OllamaClient {
+    base_url: String,
+    auth_header: Option<String>,
+    keep_alive: BTreeMap<String, KeepAlive>,
+    http: reqwest::Client,
+}
+
+impl OllamaClient {
+    pub fn load() -> Self {
+        Self::from_settings(&OllamaSettings::load())
+    }
+
+    fn from_settings(settings: &OllamaSettings) -> Self {
+        Self {
+            base_url: settings.url.clone(),
+            auth_header: settings.auth_header(),
+            keep_alive: settings.keep_alive.clone(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The keep-alive pinned for `model`, if any.
+    pub fn keep_alive(&self, model: &str) -> Option<&KeepAlive> {
+        self.keep_alive.get(model)
+    }
+
+    pub fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.with_auth(self.http.get(format!("{}{}", self.base_url, path)))
+    }
+
+    pub fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.with_auth(self.http.post(format!("{}{}", self.base_url, path)))
+    }
+
+    pub fn head(&self, path: &str) -> reqwest::RequestBuilder {
+        self.with_auth(self.http.head(format!("{}{}", self.base_url, path)))
+    }
+
+    pub fn delete(&self, path: &str) -> reqwest::RequestBuilder {
+        self.with_auth(self.http.delete(format!("{}{}", self.base_url, path)))
+    }
+
+    fn with_auth(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth_header {
+            Some(value) => builder.header(reqwest::header::AUTHORIZATION, value),
+            None => builder,
+        }
+    }
+
+    /// Connection error message naming the endpoint we tried.
+    pub fn connect_error(&self, e: reqwest::Error) -> String {
+        error!("Failed to connect to Ollama at {}: {}", self.base_url, e);
+        format!(
+            "Failed to connect to Ollama at {}: {}. Is Ollama running?",
+            self.base_url, e
+        )
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct OllamaConnectionStatus {
+    pub url: String,
+    pub reachable: bool,
+    pub version: Option<String>,
+    pub latency_ms: Option<u128>,
+    pub error: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_ollama_settings() -> Result<serde_json::Value, String> {
+    let settings = OllamaSettings::load();
+    let has_credentials = matches!(crate::get_api_key_file(AUTH_KEY_PROVIDER), Ok(Some(_)));
+
+    Ok(serde_json::json!({
+        "url": settings.url,
+        "auth_type": settings.auth_type,
+        "username": settings.username,
+        "has_credentials": has_credentials
+    }))
+}
+
+#[tauri::command]
+pub async fn set_ollama_settings(
+    url: String,
+    auth_type: OllamaAuthType,
+    username: Option<String>,
+    secret: Option<String>,
+) -> Result<(), String> {
+    let settings = OllamaSettings {
+        url: normalize_url(&url)?,
+        auth_type,
+        username: username.filter(|u| !u.trim().is_empty()),
+        keep_alive: OllamaSettings::load().keep_alive,
+    };
+
+    if settings.auth_type == OllamaAuthType::Basic && settings.username.is_none() {
+        return Err("Basic auth requires a username".to_string());
+    }
+
+    if let Some(secret) = secret.filter(|s| !s.is_empty()) {
+        crate::store_api_key_file(AUTH_KEY_PROVIDER, &secret)?;
+    }
+    if settings.auth_type == OllamaAuthType::None {
+        crate::delete_api_key_file(AUTH_KEY_PROVIDER)?;
+    }
+
+    settings.save()
+}
+
+/// The endpoint address and keep-alive pins, for the frontend. The auth
+/// header stays here: requests that need it go through backend commands.
+#[tauri::command]
+pub async fn get_ollama_client_config() -> Result<serde_json::Value, String> {
+    let settings = OllamaSettings::load();
+    Ok(serde_json::json!({
+        "host": settings.url,
+        "keep_alive": settings.keep_alive
+    }))
+}
+
+/// Checks `/api/version` on the saved endpoint, or on `url` if given (using
+/// the saved credentials) so a new address can be tried before saving it.
+#[tauri::command]
+pub async fn test_ollama_connection(url: Option<String>) -> Result<OllamaConnectionStatus, String> {
+    let mut settings = OllamaSettings::load();
+    if let Some(url) = url {
+        settings.url = normalize_url(&url)?;
+    }
+    let client = OllamaClient::from_settings(&settings);
+    info!("Testing Ollama connection at {}", client.base_url());
+
+    let started = Instant::now();
+    let response = client
+        .get("/api/version")
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis();
+
+    let mut status = OllamaConnectionStatus {
+        url: settings.url.clone(),
+        reachable: false,
+        version: None,
+        latency_ms: None,
+        error: None,
+    };
+
+    match response {
+        Ok(resp) if resp.status().is_success() => {
+            let data = resp.json::<serde_json::Value>().await.unwrap_or_default();
+            status.reachable = true;
+            status.latency_ms = Some(latency_ms);
+            status.version = data
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string());
+            info!("Ollama reachable, version {:?}", status.version);
+        }
+        Ok(resp) if resp.status() == 401 || resp.status() == 403 => {
+            status.latency_ms = Some(latency_ms);
+            status.error = Some(format!(
+                "Authentication failed ({}). Check the Ollama credentials.",
+                resp.status()
+            ));
+        }
+        Ok(resp) => {
+            status.latency_ms = Some(latency_ms);
+            status.error = Some(format!(
+                "Unexpected response from Ollama: {}",
+                resp.status()
+            ));
+        }
+        Err(e) => {
+            error!("Ollama connection test failed: {}", e);
+            status.error = Some(if e.is_timeout() {
+                "Connection timed out".to_string()
+            } else {
+                format!("Could not connect: {}", e)
+            });
+        }
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_keep_alive_durations() {
+        for valid in ["10m", "1h30m", "-1s", "1.5h", "300ms", "0"] {
+            assert!(
+                KeepAlive::Duration(valid.to_string()).validate().is_ok(),
+                "{}",
+                valid
+            );
+        }
+        for invalid in ["10 minutes", "10", "m", "", "1h-5m", "1d"] {
+            assert!(
+                KeepAlive::Duration(invalid.to_string()).validate().is_err(),
+                "{}",
+                invalid
+            );
+        }
+        assert!(KeepAlive::Seconds(-1).validate().is_ok());
+    }
+
+    #[test]
+    fn bare_host_gets_ollama_port() {
+        assert_eq!(normalize_url("myhost").unwrap(), "http://myhost:11434");
+        assert_eq!(normalize_url("0.0.0.0").unwrap(), "http://0.0.0.0:11434");
+        assert_eq!(normalize_url("myhost:8080/").unwrap(), "http://myhost:8080");
+        assert_eq!(normalize_url("https://myhost").unwrap(), "https://myhost");
+    }
+}
@@ -0,0 +1,787 @@
+// Ollama model management
+//
+// Pull, create, delete, copy and inspect models on the configured endpoint.
+// Pulls and creates stream their progress over a channel and are registered
+// with the `StreamRegistry`, so `cancel_stream` stops them like a chat stream.
+// Local GGUF files reach Ollama as blobs named by their sha256 digest, which
+// `/api/create` then refers to.
+// Capabilities read from `/api/show` are cached by digest, which changes
+// whenever a model is pulled again or recreated. Loading and unloading go
+// through `/api/generate` without a prompt, which only sets the keep-alive.
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use tauri::ipc::Channel;
+
+use super::{KeepAlive, OllamaClient, OllamaSettings};
+use crate::providers::{read_ndjson, StreamRegistry};
+
+/// Messages sent over a pull's or create's channel, tagged like chat stream
+/// events.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum PullEvent {
+    /// First message, carrying the id `cancel_stream` takes.
+    Started {
+        request_id: u64,
+    },
+    /// A step without byte counts, e.g. "pulling manifest",
+    /// "uploading model.gguf" or "writing manifest".
+    Status {
+        status: String,
+    },
+    /// Download progress of one layer.
+    Progress {
+        digest: String,
+        completed: u64,
+        total: u64,
+    },
+    Done {
+        model: String,
+    },
+    /// Sent instead of `Done` when cancelled. Layers already downloaded or
+    /// uploaded are kept and a later attempt resumes from them.
+    Cancelled {
+        request_id: u64,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct PullLine {
+    #[serde(default)]
+    status: String,
+    digest: Option<String>,
+    total: Option<u64>,
+    completed: Option<u64>,
+    error: Option<String>,
+}
+
+/// What the app needs to know about a model to pick its features.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ModelCapabilities {
+    pub context_length: Option<u64>,
+    pub family: Option<String>,
+    /// "completion", "tools", "vision", "thinking", "embedding", ...
+    pub capabilities: Option<Vec<String>>,
+    /// The chat template, in Go template syntax
+    pub template: Option<String>,
+}
+
+impl ModelCapabilities {
+    pub fn from_show(data: &serde_json::Value) -> Self {
+        let model_info = &data["model_info"];
+        // Keys are prefixed with the architecture, e.g. "llama.context_length"
+        let context_length = model_info["general.architecture"]
+            .as_str()
+            .and_then(|arch| model_info[format!("{}.context_length", arch).as_str()].as_u64());
+        let template = data["template"]
+            .as_str()
+            .filter(|template| !template.is_empty())
+            .map(|template| template.to_string());
+
+        // Older Ollama versions don't list capabilities; fall back to what
+        // the template and projector reveal
+        let capabilities = match data["capabilities"].as_array() {
+            Some(list) => list
+                .iter()
+                .filter_map(|capability| capability.as_str())
+                .map(|capability| capability.to_string())
+                .collect(),
+            None => {
+                let mut capabilities = vec!["completion".to_string()];
+                if template.as_deref().is_some_and(|t| t.contains(".Tools")) {
+                    capabilities.push("tools".to_string());
+                }
+                if data["projector_info"].is_object() {
+                    capabilities.push("vision".to_string());
+                }
+                capabilities
+            }
+        };
+
+        Self {
+            context_length,
+            family: data["details"]["family"].as_str().map(|f| f.to_string()),
+            capabilities: Some(capabilities),
+            template,
+        }
+    }
+}
+
+static CAPABILITIES: LazyLock<Mutex<HashMap<String, ModelCapabilities>>> =
+    LazyLock::new(Default::default);
+
+/// Capabilities of `model`, from the cache when its digest is unchanged.
+pub async fn model_capabilities(
+    client: &OllamaClient,
+    model: &str,
+    digest: &str,
+) -> Result<ModelCapabilities, String> {
+    let cached = CAPABILITIES
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(digest).cloned());
+    if let Some(capabilities) = cached {
+        return Ok(capabilities);
+    }
+
+    let capabilities = ModelCapabilities::from_show(&show(client, model).await?);
+    if let Ok(mut cache) = CAPABILITIES.lock() {
+        cache.insert(digest.to_string(), capabilities.clone());
+    }
+    Ok(capabilities)
+}
+
+/// A model resident in memory, from `/api/ps`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunningModel {
+    pub name: String,
+    #[serde(default)]
+    pub digest: String,
+    /// Total bytes in memory; what isn't in `size_vram` is in system RAM
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub size_vram: u64,
+    /// When Ollama unloads the model; far in the future when pinned
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RunningModels {
+    #[serde(default)]
+    models: Vec<RunningModel>,
+}
+
+fn send(channel: &Channel<PullEvent>, event: PullEvent) {
+    if let Err(e) = channel.send(event) {
+        error!("Failed to send pull event: {}", e);
+    }
+}
+
+/// Passes successful responses through; Ollama errors come back as
+/// `{ "error": "..." }`.
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|data| data["error"].as_str().map(|e| e.to_string()))
+        .unwrap_or(body);
+    error!("Ollama API error {}: {}", status, message);
+    Err(format!("Ollama API error: {}", message))
+}
+
+/// Raw `/api/show` response for `model`.
+pub async fn show(client: &OllamaClient, model: &str) -> Result<serde_json::Value, String> {
+    let response = client
+        .post("/api/show")
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    check(response)
+        .await?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse Ollama response: {}", e))
+}
+
+/// Loads `model` into memory, or unloads it with a zero keep-alive.
+/// Without a keep-alive Ollama applies its default (five minutes).
+async fn set_loaded(
+    client: &OllamaClient,
+    model: &str,
+    keep_alive: Option<&KeepAlive>,
+) -> Result<(), String> {
+    let mut body = serde_json::json!({ "model": model, "stream": false });
+    if let Some(keep_alive) = keep_alive {
+        body["keep_alive"] = serde_json::json!(keep_alive);
+    }
+
+    let response = client
+        .post("/api/generate")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    check(response).await?;
+    Ok(())
+}
+
+/// Forwards the NDJSON progress of a pull or create to `on_event`.
+async fn read_progress(
+    response: reqwest::Response,
+    on_event: &Channel<PullEvent>,
+) -> Result<(), String> {
+    let mut progress_error = None;
+    read_ndjson(response, "Ollama", |line| {
+        match serde_json::from_str::<PullLine>(line) {
+            Ok(PullLine { error: Some(e), .. }) => {
+                error!("Ollama progress error: {}", e);
+                progress_error = Some(e);
+            }
+            // Layers report a total right away and completed once bytes arrive
+            Ok(PullLine {
+                digest: Some(digest),
+                total: Some(total),
+                completed,
+                ..
+            }) => send(
+                on_event,
+                PullEvent::Progress {
+                    digest,
+                    completed: completed.unwrap_or(0),
+                    total,
+                },
+            ),
+            Ok(PullLine { status, .. }) => send(on_event, PullEvent::Status { status }),
+            Err(e) => {
+                error!(
+                    "Failed to parse Ollama progress line: {} - Error: {}",
+                    line, e
+                );
+            }
+        }
+    })
+    .await?;
+
+    match progress_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+async fn pull(
+    client: &OllamaClient,
+    model: &str,
+    insecure: bool,
+    on_event: &Channel<PullEvent>,
+) -> Result<(), String> {
+    let response = client
+        .post("/api/pull")
+        .json(&serde_json::json!({
+            "model": model,
+            "insecure": insecure,
+            "stream": true
+        }))
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    let response = check(response).await?;
+
+    read_progress(response, on_event)
+        .await
+        .map_err(|e| format!("Failed to pull {}: {}", model, e))
+}
+
+/// What a derived model is made of: the instructions of a Modelfile.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ModelRecipe {
+    /// Name of an installed model, or the path of a local `.gguf` file
+    pub from: String,
+    #[serde(default)]
+    pub system: Option<String>,
+    /// Chat template, in Go template syntax
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Default options, e.g. `{"num_ctx": 8192, "stop": ["<|end|>"]}`
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+    /// Path of a LoRA adapter in GGUF format
+    #[serde(default)]
+    pub adapter: Option<String>,
+}
+
+fn is_gguf(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"))
+}
+
+/// Quotes a Modelfile argument; triple quotes allow newlines. Triple quotes
+/// have no escapes, so text that would close them early is rejected rather
+/// than letting the rest of it read as Modelfile instructions.
+fn quote(value: &str) -> Result<String, String> {
+    if !value.contains('\n') && !value.contains('"') && !value.contains('\\') {
+        return Ok(format!("\"{}\"", value));
+    }
+    if value.contains("\"\"\"") || value.ends_with('"') {
+        return Err(format!(
+            "Text can't contain \"\"\" or end with a quote in a Modelfile: {}",
+            value.lines().next().unwrap_or_default()
+        ));
+    }
+    Ok(format!("\"\"\"{}\"\"\"", value))
+}
+
+/// Paths and names go in unquoted, so they must stay on their line.
+fn single_line<'a>(what: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.contains(['\n', '\r']) {
+        return Err(format!("The {} can't span several lines", what));
+    }
+    Ok(value)
+}
+
+impl ModelRecipe {
+    /// The recipe as Modelfile text, for review or `ollama create -f`.
+    pub fn modelfile(&self) -> Result<String, String> {
+        let mut lines = vec![format!("FROM {}", single_line("base model", &self.from)?)];
+        if let Some(adapter) = &self.adapter {
+            lines.push(format!("ADAPTER {}", single_line("adapter", adapter)?));
+        }
+        if let Some(template) = &self.template {
+            lines.push(format!("TEMPLATE {}", quote(template)?));
+        }
+        if let Some(system) = &self.system {
+            lines.push(format!("SYSTEM {}", quote(system)?));
+        }
+        for (name, value) in &self.parameters {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid parameter name '{}'", name));
+            }
+            // Lists like `stop` take one line per value
+            let values = match value {
+                serde_json::Value::Array(values) => values.clone(),
+                value => vec![value.clone()],
+            };
+            for value in values {
+                let value = match value {
+                    serde_json::Value::String(text) => quote(&text)?,
+                    value => value.to_string(),
+                };
+                lines.push(format!("PARAMETER {} {}", name, value));
+            }
+        }
+        Ok(lines.join("\n") + "\n")
+    }
+}
+
+/// Hashes the file on a blocking thread; GGUF files run to many gigabytes.
+async fn file_digest(path: &str) -> Result<String, String> {
+    let owned = path.to_string();
+    tokio::task::spawn_blocking(move || {
+        let path = owned;
+        let mut file =
+            std::fs::File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(format!("sha256:{}", hash))
+    })
+    .await
+    .map_err(|e| format!("Failed to hash {}: {}", path, e))?
+}
+
+/// Makes the local file at `path` available to `/api/create`, uploading it
+/// unless Ollama already has a blob with its digest. Returns the file name
+/// and digest for the `files` or `adapters` map.
+async fn upload_blob(
+    client: &OllamaClient,
+    path: &str,
+    on_event: &Channel<PullEvent>,
+) -> Result<(String, String), String> {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    send(
+        on_event,
+        PullEvent::Status {
+            status: format!("hashing {}", name),
+        },
+    );
+    let digest = file_digest(path).await?;
+    let blob_path = format!("/api/blobs/{}", digest);
+
+    let existing = client
+        .head(&blob_path)
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    if existing.status().is_success() {
+        info!("Ollama already has {} as {}", name, digest);
+        return Ok((name, digest));
+    }
+
+    send(
+        on_event,
+        PullEvent::Status {
+            status: format!("uploading {}", name),
+        },
+    );
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let response = client
+        .post(&blob_path)
+        .body(file)
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    check(response).await?;
+    info!("Uploaded {} as {}", name, digest);
+    Ok((name, digest))
+}
+
+async fn create(
+    client: &OllamaClient,
+    model: &str,
+    recipe: &ModelRecipe,
+    on_event: &Channel<PullEvent>,
+) -> Result<(), String> {
+    let mut body = serde_json::json!({ "model": model, "stream": true });
+    if is_gguf(&recipe.from) {
+        let (name, digest) = upload_blob(client, &recipe.from, on_event).await?;
+        body["files"] = serde_json::json!({ name: digest });
+    } else {
+        body["from"] = serde_json::json!(recipe.from);
+    }
+    if let Some(adapter) = &recipe.adapter {
+        let (name, digest) = upload_blob(client, adapter, on_event).await?;
+        body["adapters"] = serde_json::json!({ name: digest });
+    }
+    if let Some(system) = &recipe.system {
+        body["system"] = serde_json::json!(system);
+    }
+    if let Some(template) = &recipe.template {
+        body["template"] = serde_json::json!(template);
+    }
+    if !recipe.parameters.is_empty() {
+        body["parameters"] = serde_json::json!(recipe.parameters);
+    }
+
+    let response = client
+        .post("/api/create")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    let response = check(response).await?;
+
+    read_progress(response, on_event)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", model, e))
+}
+
+// Commands
+
+/// Downloads `model`, streaming progress over `on_event`. Returns the
+/// request id once the pull finished or was cancelled.
+#[tauri::command]
+pub async fn pull_ollama_model(
+    streams: tauri::State<'_, StreamRegistry>,
+    on_event: Channel<PullEvent>,
+    model: String,
+    insecure: Option<bool>,
+) -> Result<u64, String> {
+    let client = OllamaClient::load();
+    info!("Pulling Ollama model {} from {}", model, client.base_url());
+
+    // Registered apart from "ollama" so abort_ollama leaves pulls running
+    let (request_id, result) = streams
+        .track(
+            "ollama_pull",
+            |request_id| send(&on_event, PullEvent::Started { request_id }),
+            pull(&client, &model, insecure.unwrap_or(false), &on_event),
+        )
+        .await;
+
+    match result {
+        Some(result) => {
+            result?;
+            info!("Pulled Ollama model {}", model);
+            send(&on_event, PullEvent::Done { model });
+        }
+        None => {
+            info!("Pull of {} cancelled", model);
+            send(&on_event, PullEvent::Cancelled { request_id });
+        }
+    }
+    Ok(request_id)
+}
+
+/// Creates `model` from `recipe`, streaming status over `on_event` like a
+/// pull. Returns the request id once it finished or was cancelled.
+#[tauri::command]
+pub async fn create_ollama_model(
+    streams: tauri::State<'_, StreamRegistry>,
+    on_event: Channel<PullEvent>,
+    model: String,
+    recipe: ModelRecipe,
+) -> Result<u64, String> {
+    let client = OllamaClient::load();
+    // The fields go to /api/create as JSON, so text the Modelfile can't
+    // quote is fine here; it only leaves the preview out of the log
+    match recipe.modelfile() {
+        Ok(modelfile) => info!(
+            "Creating Ollama model {} on {} from:\n{}",
+            model,
+            client.base_url(),
+            modelfile
+        ),
+        Err(_) => info!(
+            "Creating Ollama model {} on {} from {}",
+            model,
+            client.base_url(),
+            recipe.from
+        ),
+    }
+
+    let (request_id, result) = streams
+        .track(
+            "ollama_create",
+            |request_id| send(&on_event, PullEvent::Started { request_id }),
+            create(&client, &model, &recipe, &on_event),
+        )
+        .await;
+
+    match result {
+        Some(result) => {
+            result?;
+            info!("Created Ollama model {}", model);
+            send(&on_event, PullEvent::Done { model });
+        }
+        None => {
+            info!("Creation of {} cancelled", model);
+            send(&on_event, PullEvent::Cancelled { request_id });
+        }
+    }
+    Ok(request_id)
+}
+
+/// `recipe` as Modelfile text, for review or export. Creating the model
+/// doesn't go through it, so text it can't quote only fails here.
+#[tauri::command]
+pub fn build_ollama_modelfile(recipe: ModelRecipe) -> Result<String, String> {
+    recipe.modelfile()
+}
+
+#[tauri::command]
+pub async fn delete_ollama_model(model: String) -> Result<(), String> {
+    let client = OllamaClient::load();
+    info!("Deleting Ollama model {}", model);
+
+    let response = client
+        .delete("/api/delete")
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    check(response).await?;
+    Ok(())
+}
+
+/// Copies `source` to a new name, e.g. to give a model a shorter tag.
+#[tauri::command]
+pub async fn copy_ollama_model(source: String, destination: String) -> Result<(), String> {
+    let client = OllamaClient::load();
+    info!("Copying Ollama model {} to {}", source, destination);
+
+    let response = client
+        .post("/api/copy")
+        .json(&serde_json::json!({
+            "source": source,
+            "destination": destination
+        }))
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+    check(response).await?;
+    Ok(())
+}
+
+/// Model details as Ollama reports them: Modelfile, parameters, template,
+/// details and model_info.
+#[tauri::command]
+pub async fn show_ollama_model(model: String) -> Result<serde_json::Value, String> {
+    let client = OllamaClient::load();
+    info!("Showing Ollama model {}", model);
+    show(&client, &model).await
+}
+
+/// Models currently loaded by Ollama, with their memory use and expiry.
+#[tauri::command]
+pub async fn list_running_ollama_models() -> Result<Vec<RunningModel>, String> {
+    let client = OllamaClient::load();
+    let response = client
+        .get("/api/ps")
+        .send()
+        .await
+        .map_err(|e| client.connect_error(e))?;
+
+    let running = check(response)
+        .await?
+        .json::<RunningModels>()
+        .await
+        .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+    Ok(running.models)
+}
+
+/// Loads `model` ahead of the first chat so that reply doesn't wait for it.
+/// `keep_alive` defaults to the model's pinned value.
+#[tauri::command]
+pub async fn load_ollama_model(model: String, keep_alive: Option<KeepAlive>) -> Result<(), String> {
+    let client = OllamaClient::load();
+    if let Some(keep_alive) = &keep_alive {
+        keep_alive.validate()?;
+    }
+    let keep_alive = keep_alive.or_else(|| client.keep_alive(&model).cloned());
+    info!(
+        "Loading Ollama model {} (keep alive {:?})",
+        model, keep_alive
+    );
+
+    let started = std::time::Instant::now();
+    set_loaded(&client, &model, keep_alive.as_ref()).await?;
+    info!("Loaded {} in {} ms", model, started.elapsed().as_millis());
+    Ok(())
+}
+
+/// Unloads `model` from memory now. A pinned model is loaded again by the
+/// next request for it.
+#[tauri::command]
+pub async fn unload_ollama_model(model: String) -> Result<(), String> {
+    let client = OllamaClient::load();
+    info!("Unloading Ollama model {}", model);
+    set_loaded(&client, &model, Some(&KeepAlive::Seconds(0))).await
+}
+
+/// Pins `model` in memory for `keep_alive` (e.g. "1h", or -1 for as long as
+/// Ollama runs) and loads it; every later request repeats the value. `None`
+/// removes the pin, leaving the model to Ollama's default expiry.
+#[tauri::command]
+pub async fn set_ollama_keep_alive(
+    model: String,
+    keep_alive: Option<KeepAlive>,
+) -> Result<(), String> {
+    if let Some(keep_alive) = &keep_alive {
+        keep_alive.validate()?;
+    }
+
+    let mut settings = OllamaSettings::load();
+    match &keep_alive {
+        Some(keep_alive) => {
+            settings
+                .keep_alive
+                .insert(model.clone(), keep_alive.clone());
+        }
+        None => {
+            settings.keep_alive.remove(&model);
+        }
+    }
+    settings.save()?;
+
+    if let Some(keep_alive) = &keep_alive {
+        info!("Pinning Ollama model {} for {:?}", model, keep_alive);
+        set_loaded(&OllamaClient::load(), &model, Some(keep_alive)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(system: &str) -> ModelRecipe {
+        ModelRecipe {
+            from: "llama3.2".to_string(),
+            system: Some(system.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quotes_multi_line_text() {
+        let modelfile = recipe("Be brief.\nSay \"hi\" first.").modelfile().unwrap();
+
+        assert_eq!(
+            modelfile,
+            "FROM llama3.2\nSYSTEM \"\"\"Be brief.\nSay \"hi\" first.\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn rejects_text_closing_triple_quotes() {
+        let injected = "Be brief.\"\"\"\nADAPTER /tmp/evil.gguf\nSYSTEM \"\"\"x";
+
+        assert!(recipe(injected).modelfile().is_err());
+        assert!(recipe("Answer with \"yes\"").modelfile().is_err());
+    }
+
+    #[test]
+    fn reads_capabilities_from_show() {
+        let data = serde_json::json!({
+            "modelfile": "FROM llama3.2:3b",
+            "parameters": "stop \"<|eot_id|>\"",
+            "template": "{{- if .Tools }}...{{ end }}",
+            "details": {
+                "format": "gguf",
+                "family": "llama",
+                "parameter_size": "3.2B",
+                "quantization_level": "Q4_K_M"
+            },
+            "model_info": {
+                "general.architecture": "llama",
+                "general.parameter_count": 3212749888u64,
+                "llama.context_length": 131072,
+                "llama.embedding_length": 3072
+            },
+            "capabilities": ["completion", "tools"]
+        });
+
+        let capabilities = ModelCapabilities::from_show(&data);
+        assert_eq!(capabilities.context_length, Some(131072));
+        assert_eq!(capabilities.family.as_deref(), Some("llama"));
+        assert_eq!(
+            capabilities.capabilities,
+            Some(vec!["completion".to_string(), "tools".to_string()])
+        );
+        assert_eq!(
+            capabilities.template.as_deref(),
+            Some("{{- if .Tools }}...{{ end }}")
+        );
+    }
+
+    #[test]
+    fn guesses_capabilities_without_the_list() {
+        let data = serde_json::json!({
+            "template": "{{ range .Tools }}{{ . }}{{ end }}",
+            "details": { "family": "mllama" },
+            "model_info": { "general.architecture": "mllama" },
+            "projector_info": { "general.architecture": "clip" }
+        });
+
+        let capabilities = ModelCapabilities::from_show(&data);
+        assert_eq!(capabilities.context_length, None);
+        assert_eq!(
+            capabilities.capabilities,
+            Some(vec![
+                "completion".to_string(),
+                "tools".to_string(),
+                "vision".to_string()
+            ])
+        );
+
+        let plain = ModelCapabilities::from_show(&serde_json::json!({ "template": "" }));
+        assert_eq!(plain.capabilities, Some(vec!["completion".to_string()]));
+        assert_eq!(plain.template, None);
+    }
+}
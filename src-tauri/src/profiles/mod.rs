@@ -0,0 +1,293 @@
+// Per-model parameter profiles
+//
+// Default runtime options per provider and model id, kept in
+// ~/.olly/profiles.json and cached in the managed `ProfileStore`. Every chat
+// request for the model starts from its profile: options sent with the
+// request win, the profile fills in what they leave unset. Models without a
+// saved profile get suggested defaults from their metadata, which apply only
+// once saved: for Ollama the Modelfile's parameters and a context window
+// sized from the trained length, for cloud models their output limit.
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::ollama::models::{show, ModelCapabilities};
+use crate::ollama::OllamaClient;
+use crate::providers::GenerationOptions;
+
+/// A full trained window costs a lot of memory, so suggest at most this much.
+const SUGGESTED_NUM_CTX: u64 = 8192;
+
+/// Cloud answers rarely need more, and Claude refuses larger limits on
+/// requests that don't stream.
+const SUGGESTED_MAX_TOKENS: u32 = 8192;
+
+/// Output token limits by provider and model id prefix; the longest
+/// matching prefix wins.
+const MAX_OUTPUT_TOKENS: &[(&str, &str, u32)] = &[
+    ("claude", "claude-3-haiku", 4096),
+    ("claude", "claude-3-5", 8192),
+    ("claude", "claude-3-7-sonnet", 64000),
+    ("claude", "claude-sonnet-4", 64000),
+    ("claude", "claude-opus-4", 32000),
+    ("claude", "claude-haiku-4", 64000),
+    ("openai", "gpt-4o", 16384),
+    ("openai", "gpt-4.1", 32768),
+    ("openai", "o3", 100000),
+    ("openai", "o4-mini", 100000),
+    ("gemini", "gemini-1.5", 8192),
+    ("gemini", "gemini-2.0", 8192),
+    ("gemini", "gemini-2.5", 65536),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ModelProfile {
+    /// Answer length limit; `num_predict` on Ollama
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Ollama only, like the two below
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_thread: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+}
+
+impl ModelProfile {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fills the options' unset fields from the profile.
+    pub fn apply(&self, options: &mut GenerationOptions) {
+        options.max_tokens = options.max_tokens.or(self.max_tokens);
+        options.temperature = options.temperature.or(self.temperature);
+        options.num_ctx = options.num_ctx.or(self.num_ctx);
+        options.num_thread = options.num_thread.or(self.num_thread);
+        options.repeat_penalty = options.repeat_penalty.or(self.repeat_penalty);
+    }
+
+    /// Suggested profile for an Ollama model from its `/api/show` response.
+    fn from_show(data: &serde_json::Value) -> Self {
+        // One "name value" pair per line, as in the Modelfile
+        let parameters: BTreeMap<&str, &str> = data["parameters"]
+            .as_str()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.trim().split_once(char::is_whitespace))
+            .map(|(name, value)| (name, value.trim()))
+            .collect();
+        fn parameter<T: FromStr>(parameters: &BTreeMap<&str, &str>, name: &str) -> Option<T> {
+            parameters.get(name).and_then(|value| value.parse().ok())
+        }
+
+        let trained_ctx = ModelCapabilities::from_show(data).context_length;
+        Self {
+            max_tokens: parameter(&parameters, "num_predict"),
+            temperature: parameter(&parameters, "temperature"),
+            num_ctx: parameter(&parameters, "num_ctx")
+                .or_else(|| trained_ctx.map(|length| length.min(SUGGESTED_NUM_CTX) as u32)),
+            num_thread: parameter(&parameters, "num_thread"),
+            repeat_penalty: parameter(&parameters, "repeat_penalty"),
+        }
+    }
+}
+
+fn get_profiles_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".olly").join("profiles.json")
+}
+
+// Profiles by provider id, then model id
+type Profiles = BTreeMap<String, BTreeMap<String, ModelProfile>>;
+
+fn load_profiles() -> Profiles {
+    match fs::read_to_string(get_profiles_path()) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            error!("Failed to parse model profiles, ignoring them: {}", e);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn save_profiles(profiles: &Profiles) -> Result<(), String> {
+    let path = get_profiles_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let data = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize model profiles: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write model profiles: {}", e))
+}
+
+/// Saved profiles, read from disk on first use so chat requests don't parse
+/// the file each time. `set` writes through to disk.
+#[derive(Default)]
+pub struct ProfileStore {
+    profiles: Mutex<Option<Profiles>>,
+}
+
+impl ProfileStore {
+    /// The profile saved for `model` on `provider`, if any.
+    pub fn get(&self, provider: &str, model: &str) -> Option<ModelProfile> {
+        let mut cache = self.profiles.lock().ok()?;
+        cache
+            .get_or_insert_with(load_profiles)
+            .get(provider)?
+            .get(model)
+            .cloned()
+    }
+
+    /// Saves the profile of `model` on `provider`; `None` removes it.
+    fn set(
+        &self,
+        provider: &str,
+        model: &str,
+        profile: Option<ModelProfile>,
+    ) -> Result<(), String> {
+        let mut cache = self
+            .profiles
+            .lock()
+            .map_err(|_| "Model profiles are unavailable".to_string())?;
+        // Start from the file, in case it was edited since it was cached
+        let mut profiles = load_profiles();
+        match profile {
+            Some(profile) => {
+                profiles
+                    .entry(provider.to_string())
+                    .or_default()
+                    .insert(model.to_string(), profile);
+            }
+            None => {
+                if let Some(models) = profiles.get_mut(provider) {
+                    models.remove(model);
+                    if models.is_empty() {
+                        profiles.remove(provider);
+                    }
+                }
+            }
+        }
+        save_profiles(&profiles)?;
+        *cache = Some(profiles);
+        Ok(())
+    }
+}
+
+async fn suggested_profile(provider: &str, model: &str) -> ModelProfile {
+    if provider == "ollama" {
+        return match show(&OllamaClient::load(), model).await {
+            Ok(data) => ModelProfile::from_show(&data),
+            Err(e) => {
+                error!("Failed to read metadata of {}: {}", model, e);
+                ModelProfile::default()
+            }
+        };
+    }
+
+    let max_tokens = MAX_OUTPUT_TOKENS
+        .iter()
+        .filter(|(p, prefix, _)| *p == provider && model.starts_with(prefix))
+        .max_by_key(|(_, prefix, _)| prefix.len())
+        .map(|(_, _, limit)| (*limit).min(SUGGESTED_MAX_TOKENS));
+    ModelProfile {
+        max_tokens,
+        ..Default::default()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProfileInfo {
+    #[serde(flatten)]
+    pub profile: ModelProfile,
+    /// False when `profile` holds suggested defaults that don't apply yet
+    pub saved: bool,
+}
+
+// Commands
+
+/// The saved profile of `model` on `provider`, or suggested defaults.
+#[tauri::command]
+pub async fn get_model_profile(
+    profiles: tauri::State<'_, ProfileStore>,
+    provider: String,
+    model: String,
+) -> Result<ProfileInfo, String> {
+    if let Some(profile) = profiles.get(&provider, &model) {
+        return Ok(ProfileInfo {
+            profile,
+            saved: true,
+        });
+    }
+
+    Ok(ProfileInfo {
+        profile: suggested_profile(&provider, &model).await,
+        saved: false,
+    })
+}
+
+/// Saves the profile of `model` on `provider`; `None` or an empty profile
+/// removes it.
+#[tauri::command]
+pub async fn set_model_profile(
+    profiles: tauri::State<'_, ProfileStore>,
+    provider: String,
+    model: String,
+    profile: Option<ModelProfile>,
+) -> Result<(), String> {
+    if let Some(temperature) = profile.as_ref().and_then(|p| p.temperature) {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(format!(
+                "Temperature must be between 0 and 2, got {}",
+                temperature
+            ));
+        }
+    }
+
+    let profile = profile.filter(|profile| !profile.is_empty());
+    match &profile {
+        Some(profile) => info!(
+            "Saving parameter profile for {} on {}: {:?}",
+            model, provider, profile
+        ),
+        None => info!("Removing parameter profile for {} on {}", model, provider),
+    }
+    profiles.set(&provider, &model, profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_ollama_profile_from_show() {
+        let data = serde_json::json!({
+            "parameters": "stop                           \"<|eot_id|>\"\ntemperature                    0.6\nnum_thread                     8",
+            "model_info": {
+                "general.architecture": "llama",
+                "llama.context_length": 131072
+            }
+        });
+
+        assert_eq!(
+            ModelProfile::from_show(&data),
+            ModelProfile {
+                temperature: Some(0.6),
+                num_ctx: Some(8192),
+                num_thread: Some(8),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,1183 @@
+// Claude API
+//
+// Besides the opt-in server-side web search tool, Claude gets the client
+// tools from `crate::tools`. When a turn stops with `tool_use` we run the
+// tools, send the results back and continue, until Claude ends the turn.
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::{
+    check_response, map_send_error, push_citation, read_sse, ChatRequest, ChatResponse, Citation,
+    DocumentCitation, DocumentLocation, LlmProvider, ProviderCapabilities, StreamSink, Usage,
+    UserLocation, WebSearchOptions,
+};
+use crate::tools::{self, ToolDefinition};
+
+const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+const MODELS_URL: &str = "https://api.anthropic.com/v1/models?limit=20";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+// Upper bound on tool round trips within one chat turn
+const MAX_TOOL_ROUNDS: usize = 5;
+
+// Upper bound on follow-up requests continuing one truncated answer
+const MAX_CONTINUATIONS: usize = 3;
+
+// Extended thinking budgets; Claude rejects anything under the minimum
+const DEFAULT_THINKING_BUDGET: u32 = 4096;
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+#[derive(Serialize)]
+struct ClaudeRequest<'a> {
+    model: &'a str,
+    messages: &'a [serde_json::Value],
+    max_tokens: u32,
+    temperature: f32,
+    // A string, or text blocks when the prompt is marked for caching
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop_sequences: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Tool<'a> {
+    // Executed by Anthropic
+    WebSearch(WebSearchTool<'a>),
+    // Executed here, see `crate::tools`
+    Client(ToolDefinition),
+}
+
+#[derive(Serialize)]
+struct WebSearchTool<'a> {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_uses: Option<u32>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    allowed_domains: &'a [String],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    blocked_domains: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_location: Option<ApproximateLocation<'a>>,
+}
+
+#[derive(Serialize)]
+struct ApproximateLocation<'a> {
+    #[serde(rename = "type")]
+    location_type: &'static str,
+    #[serde(flatten)]
+    location: &'a UserLocation,
+}
+
+impl<'a> WebSearchTool<'a> {
+    fn new(options: &'a WebSearchOptions) -> Self {
+        Self {
+            tool_type: "web_search_20250305",
+            name: "web_search",
+            max_uses: options.max_uses,
+            allowed_domains: &options.allowed_domains,
+            blocked_domains: &options.blocked_domains,
+            user_location: options
+                .user_location
+                .as_ref()
+                .map(|location| ApproximateLocation {
+                    location_type: "approximate",
+                    location,
+                }),
+        }
+    }
+}
+
+/// Search hits of a `web_search_tool_result` block, or its error code.
+fn search_results(block: &serde_json::Value) -> Result<Vec<Citation>, String> {
+    match block["content"].as_array() {
+        Some(results) => Ok(results
+            .iter()
+            .filter_map(|result| {
+                let url = result["url"].as_str()?;
+                Some(Citation {
+                    url: url.to_string(),
+                    title: result["title"].as_str().map(|title| title.to_string()),
+                    ..Default::default()
+                })
+            })
+            .collect()),
+        None => Err(block["content"]["error_code"]
+            .as_str()
+            .unwrap_or("unknown_error")
+            .to_string()),
+    }
+}
+
+// Content blocks are kept as raw JSON so they can be sent back unchanged
+// (server tool results, citations) on the next tool round
+#[derive(Deserialize, Debug)]
+struct ClaudeResponse {
+    content: Vec<serde_json::Value>,
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: ClaudeUsage,
+}
+
+// message_start reports the input side, message_delta the cumulative output
+#[derive(Deserialize, Debug, Default)]
+struct ClaudeUsage {
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    cache_creation_input_tokens: Option<u64>,
+    cache_read_input_tokens: Option<u64>,
+}
+
+impl ClaudeUsage {
+    /// Overwrites the counts this event reported.
+    fn apply(&self, usage: &mut Usage) {
+        let fields = [
+            (self.input_tokens, &mut usage.input_tokens),
+            (self.output_tokens, &mut usage.output_tokens),
+            (
+                self.cache_creation_input_tokens,
+                &mut usage.cache_creation_tokens,
+            ),
+            (self.cache_read_input_tokens, &mut usage.cache_read_tokens),
+        ];
+        for (reported, total) in fields {
+            if let Some(tokens) = reported {
+                *total = tokens;
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Content {
+    #[serde(rename = "type")]
+    content_type: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    thinking: String,
+    // Text blocks grounded on web search results or documents carry their
+    // sources
+    #[serde(default)]
+    citations: Option<Vec<ClaudeCitation>>,
+    // tool_use blocks
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    input: serde_json::Value,
+}
+
+impl Content {
+    fn parse(block: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(block.clone()).ok()
+    }
+}
+
+// Streaming response structures for Claude
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum ClaudeStreamEvent {
+    #[serde(rename = "message_start")]
+    MessageStart { message: serde_json::Value },
+    #[serde(rename = "content_block_start")]
+    ContentBlockStart {
+        index: usize,
+        content_block: serde_json::Value,
+    },
+    #[serde(rename = "content_block_delta")]
+    ContentBlockDelta {
+        index: usize,
+        delta: ClaudeStreamDelta,
+    },
+    #[serde(rename = "content_block_stop")]
+    ContentBlockStop { index: usize },
+    #[serde(rename = "message_delta")]
+    MessageDelta {
+        delta: serde_json::Value,
+        #[serde(default)]
+        usage: ClaudeUsage,
+    },
+    #[serde(rename = "message_stop")]
+    MessageStop,
+    #[serde(rename = "ping")]
+    Ping,
+    #[serde(rename = "error")]
+    Error { error: serde_json::Value },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum ClaudeStreamDelta {
+    #[serde(rename = "text_delta")]
+    TextDelta { text: String },
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { thinking: String },
+    // Signs the thinking block, which must be sent back unchanged
+    #[serde(rename = "signature_delta")]
+    SignatureDelta { signature: String },
+    #[serde(rename = "citations_delta")]
+    CitationsDelta { citation: serde_json::Value },
+    // Tool input arrives as JSON fragments, complete at content_block_stop
+    #[serde(rename = "input_json_delta")]
+    InputJsonDelta { partial_json: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClaudeCitation {
+    WebSearchResultLocation {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        cited_text: Option<String>,
+    },
+    // Document citations, in the units of the document type
+    CharLocation {
+        #[serde(flatten)]
+        document: CitedDocument,
+        start_char_index: u64,
+        end_char_index: u64,
+    },
+    PageLocation {
+        #[serde(flatten)]
+        document: CitedDocument,
+        start_page_number: u64,
+        end_page_number: u64,
+    },
+    ContentBlockLocation {
+        #[serde(flatten)]
+        document: CitedDocument,
+        start_block_index: u64,
+        end_block_index: u64,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct CitedDocument {
+    #[serde(default)]
+    cited_text: Option<String>,
+    document_index: usize,
+    #[serde(default)]
+    document_title: Option<String>,
+}
+
+impl CitedDocument {
+    fn to_citation(&self, location: DocumentLocation) -> Citation {
+        Citation {
+            url: String::new(),
+            title: self.document_title.clone(),
+            cited_text: self.cited_text.clone(),
+            document: Some(DocumentCitation {
+                index: self.document_index,
+                title: self.document_title.clone(),
+                location,
+            }),
+        }
+    }
+}
+
+impl ClaudeCitation {
+    fn to_citation(&self) -> Option<Citation> {
+        match self {
+            ClaudeCitation::WebSearchResultLocation {
+                url,
+                title,
+                cited_text,
+            } => Some(Citation {
+                url: url.clone(),
+                title: title.clone(),
+                cited_text: cited_text.clone(),
+                document: None,
+            }),
+            ClaudeCitation::CharLocation {
+                document,
+                start_char_index,
+                end_char_index,
+            } => Some(document.to_citation(DocumentLocation::Char {
+                start: *start_char_index,
+                end: *end_char_index,
+            })),
+            ClaudeCitation::PageLocation {
+                document,
+                start_page_number,
+                end_page_number,
+            } => Some(document.to_citation(DocumentLocation::Page {
+                start: *start_page_number,
+                end: *end_page_number,
+            })),
+            ClaudeCitation::ContentBlockLocation {
+                document,
+                start_block_index,
+                end_block_index,
+            } => Some(document.to_citation(DocumentLocation::ContentBlock {
+                start: *start_block_index,
+                end: *end_block_index,
+            })),
+            ClaudeCitation::Other => None,
+        }
+    }
+}
+
+fn thinking_budget(request: &ChatRequest) -> Option<u32> {
+    request.options.reasoning.as_ref().map(|reasoning| {
+        reasoning
+            .budget_tokens
+            .unwrap_or(DEFAULT_THINKING_BUDGET)
+            .max(MIN_THINKING_BUDGET)
+    })
+}
+
+/// Adds text to one of the string fields of a streamed content block.
+fn append_to_block(block: Option<&mut serde_json::Value>, field: &str, text: &str) {
+    if let Some(block) = block {
+        let current = block[field].as_str().unwrap_or_default();
+        block[field] = json!(format!("{}{}", current, text));
+    }
+}
+
+fn prompt_cache(request: &ChatRequest) -> bool {
+    request.options.prompt_cache.unwrap_or(true)
+}
+
+// Cache entries live for five minutes after their last use
+fn cache_control() -> serde_json::Value {
+    json!({ "type": "ephemeral" })
+}
+
+/// Copy of `messages` with a cache breakpoint on the last content block.
+///
+/// Claude caches the whole prefix up to a breakpoint (tools, system prompt
+/// and earlier turns), so marking the newest message each time lets the next
+/// request read everything before it from the cache. Prefixes shorter than
+/// the model's minimum (1024 tokens on most models) are simply not cached.
+fn with_cache_breakpoint(messages: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    let mut messages = messages.to_vec();
+    let Some(last) = messages.last_mut() else {
+        return messages;
+    };
+
+    if let Some(text) = last["content"].as_str() {
+        last["content"] = json!([{ "type": "text", "text": text }]);
+    }
+    if let Some(block) = last["content"]
+        .as_array_mut()
+        .and_then(|blocks| blocks.last_mut())
+    {
+        block["cache_control"] = cache_control();
+    }
+    messages
+}
+
+/// Smallest history worth a cache write, in estimated tokens. Writes are
+/// billed at 1.25x input, and most models don't cache prefixes under 1024
+/// tokens at all.
+const MIN_CACHED_HISTORY_TOKENS: usize = 2048;
+
+/// Rough token count of `messages`, at four characters of JSON per token.
+fn estimated_tokens(messages: &[serde_json::Value]) -> usize {
+    messages
+        .iter()
+        .map(|message| message.to_string().len())
+        .sum::<usize>()
+        / 4
+}
+
+/// Content blocks to send back as the assistant message, without the empty
+/// text blocks Claude rejects.
+fn non_empty_blocks(blocks: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    blocks
+        .iter()
+        .filter(|block| block["type"] != "text" || block["text"] != "")
+        .cloned()
+        .collect()
+}
+
+/// The partial assistant message to prefill when continuing a truncated
+/// answer, with the trailing whitespace cut from it, or `None` when it ends
+/// in something other than text (e.g. a cut off tool call).
+fn continuation_prefill(blocks: &[serde_json::Value]) -> Option<(Vec<serde_json::Value>, String)> {
+    let mut prefill = non_empty_blocks(blocks);
+
+    // Claude rejects a prefill ending in whitespace
+    let last = prefill.last_mut().filter(|block| block["type"] == "text")?;
+    let full = last["text"].as_str()?;
+    let text = full.trim_end();
+    if text.is_empty() {
+        return None;
+    }
+    let cut = full[text.len()..].to_string();
+    last["text"] = json!(text);
+    Some((prefill, cut))
+}
+
+/// One assistant message: its content blocks and why it stopped.
+struct Turn {
+    blocks: Vec<serde_json::Value>,
+    stop_reason: Option<String>,
+}
+
+/// Text and sources accumulated over all rounds of a chat turn.
+#[derive(Default)]
+struct TurnOutput {
+    content: String,
+    reasoning: String,
+    citations: Vec<Citation>,
+    // Summed over all rounds
+    usage: Usage,
+    // Of the last request
+    stop_reason: Option<String>,
+    // Whitespace already sent but cut from the continuation prefill, which
+    // Claude usually repeats at the start of the continuation
+    seam: String,
+}
+
+impl TurnOutput {
+    /// Drops leading whitespace of continued text that repeats the seam.
+    fn after_seam<'t>(&mut self, text: &'t str) -> &'t str {
+        if self.seam.is_empty() {
+            return text;
+        }
+        let mut seam = self.seam.chars();
+        let mut skipped = 0;
+        for c in text.chars() {
+            if !c.is_whitespace() || seam.next().is_none() {
+                self.seam.clear();
+                return &text[skipped..];
+            }
+            skipped += c.len_utf8();
+        }
+        self.seam = seam.collect();
+        &text[skipped..]
+    }
+
+    fn into_response(self) -> ChatResponse {
+        ChatResponse {
+            content: self.content,
+            citations: (!self.citations.is_empty()).then_some(self.citations),
+            usage: Some(self.usage),
+            stop_reason: self.stop_reason,
+            search: None,
+            reasoning: (!self.reasoning.is_empty()).then_some(self.reasoning),
+        }
+    }
+
+    /// Separates the thinking of successive rounds.
+    fn start_thinking(&mut self, sink: Option<&StreamSink>) {
+        if !self.reasoning.is_empty() && !self.reasoning.ends_with('\n') {
+            self.reasoning.push_str("\n\n");
+            if let Some(sink) = sink {
+                sink.reasoning("\n\n");
+            }
+        }
+    }
+}
+
+pub struct ClaudeProvider {
+    app: tauri::AppHandle,
+}
+
+impl ClaudeProvider {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self { app }
+    }
+
+    fn build_request<'a>(
+        &self,
+        request: &'a ChatRequest,
+        messages: &'a [serde_json::Value],
+        stream: bool,
+    ) -> ClaudeRequest<'a> {
+        let mut tools: Vec<Tool> = Vec::new();
+        if request.options.client_tools {
+            tools.extend(tools::definitions().into_iter().map(Tool::Client));
+        }
+        if let Some(web_search) = &request.options.web_search {
+            tools.push(Tool::WebSearch(WebSearchTool::new(web_search)));
+        }
+
+        // Claude has no seed parameter
+        let options = &request.options;
+        let budget = thinking_budget(request);
+        ClaudeRequest {
+            model: &request.model,
+            messages,
+            // Thinking counts against max_tokens; keep the answer's room on top
+            max_tokens: options.max_tokens.unwrap_or(1024) + budget.unwrap_or(0),
+            // Thinking only runs at the default temperature and top_k
+            temperature: match budget {
+                Some(_) => 1.0,
+                None => options.temperature.unwrap_or(0.0),
+            },
+            system: request.system_prompt().map(|system| {
+                if prompt_cache(request) {
+                    json!([{ "type": "text", "text": system, "cache_control": cache_control() }])
+                } else {
+                    json!(system)
+                }
+            }),
+            top_p: options.top_p,
+            top_k: options.top_k.filter(|_| budget.is_none()),
+            stop_sequences: &options.stop,
+            stream: stream.then_some(true),
+            tools,
+            thinking: budget
+                .map(|budget_tokens| json!({ "type": "enabled", "budget_tokens": budget_tokens })),
+        }
+    }
+
+    async fn send(&self, body: &ClaudeRequest<'_>) -> Result<reqwest::Response, String> {
+        // Load API key from secure storage, environment, or config file
+        let api_key = crate::load_api_key("claude")?;
+        info!("Using Claude model: {}", body.model);
+
+        let response = reqwest::Client::new()
+            .post(MESSAGES_URL)
+            .header("x-api-key", &api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| map_send_error(e, "Claude"))?;
+
+        check_response(response, "Claude").await
+    }
+
+    /// Runs a chat turn, looping through tool calls. Streams text and tool
+    /// progress into `sink` when given.
+    async fn run(
+        &self,
+        request: &ChatRequest,
+        sink: Option<&StreamSink>,
+    ) -> Result<ChatResponse, String> {
+        if let Some(web_search) = &request.options.web_search {
+            if !web_search.allowed_domains.is_empty() && !web_search.blocked_domains.is_empty() {
+                return Err(
+                    "Web search takes either allowed or blocked domains, not both".to_string(),
+                );
+            }
+        }
+
+        let mut messages = request
+            .turns()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to serialize messages: {}", e))?;
+        let mut output = TurnOutput::default();
+        // Blocks of the assistant message being generated, which spans
+        // several requests when it is continued
+        let mut assistant_blocks: Vec<serde_json::Value> = Vec::new();
+        let mut prefilled = false;
+        let mut tool_rounds = 0;
+        let mut continuations = 0;
+
+        loop {
+            // Only a long history is worth caching; a lone first message is
+            // never resent
+            let cached;
+            let messages_to_send = if prompt_cache(request)
+                && messages.len() > 1
+                && estimated_tokens(&messages) >= MIN_CACHED_HISTORY_TOKENS
+            {
+                cached = with_cache_breakpoint(&messages);
+                &cached
+            } else {
+                &messages
+            };
+            let body = self.build_request(request, messages_to_send, sink.is_some());
+            let turn = match sink {
+                Some(sink) => self.stream_turn(&body, sink, &mut output).await?,
+                None => self.send_turn(&body, &mut output).await?,
+            };
+
+            output.stop_reason = turn.stop_reason.clone();
+            assistant_blocks.extend(turn.blocks);
+            if prefilled {
+                messages.pop();
+                prefilled = false;
+            }
+
+            // A long server tool turn was paused: send it back unchanged and
+            // Claude resumes it. An answer that hit max_tokens is resent as
+            // a prefill to carry on from, which isn't allowed with extended
+            // thinking.
+            let stop_reason = turn.stop_reason.as_deref();
+            let prefill = match stop_reason {
+                Some("pause_turn") => Some(non_empty_blocks(&assistant_blocks)),
+                Some("max_tokens")
+                    if request.options.auto_continue && request.options.reasoning.is_none() =>
+                {
+                    match continuation_prefill(&assistant_blocks) {
+                        Some((prefill, cut)) => {
+                            output.seam = cut;
+                            Some(prefill)
+                        }
+                        None => {
+                            info!("Claude response can't be continued, stopping");
+                            break;
+                        }
+                    }
+                }
+                _ => None,
+            };
+            if let Some(prefill) = prefill {
+                if continuations == MAX_CONTINUATIONS {
+                    info!(
+                        "Claude response still unfinished after {} continuations",
+                        continuations
+                    );
+                    break;
+                }
+                continuations += 1;
+                info!("Continuing Claude response ({})", continuations);
+                messages.push(json!({ "role": "assistant", "content": prefill }));
+                prefilled = true;
+                continue;
+            }
+
+            if stop_reason != Some("tool_use") {
+                break;
+            }
+            if tool_rounds == MAX_TOOL_ROUNDS {
+                error!("Claude still requesting tools after {} rounds", tool_rounds);
+                break;
+            }
+            tool_rounds += 1;
+
+            let tool_uses: Vec<Content> = assistant_blocks
+                .iter()
+                .filter_map(Content::parse)
+                .filter(|block| block.content_type == "tool_use")
+                .collect();
+            messages.push(json!({
+                "role": "assistant",
+                "content": std::mem::take(&mut assistant_blocks)
+            }));
+
+            let mut results = Vec::new();
+            for tool_use in tool_uses {
+                if let Some(sink) = sink {
+                    sink.tool_use(&tool_use.id, &tool_use.name, &tool_use.input);
+                }
+                let result = tools::execute(&self.app, &tool_use.name, &tool_use.input).await;
+                if let Some(sink) = sink {
+                    sink.tool_result(&tool_use.id, &tool_use.name, &result);
+                }
+                results.push(json!({
+                    "type": "tool_result",
+                    "tool_use_id": tool_use.id,
+                    "content": result.content,
+                    "is_error": result.is_error
+                }));
+            }
+            messages.push(json!({ "role": "user", "content": results }));
+
+            // Keep the text before and after the tool call apart
+            if !output.content.is_empty() && !output.content.ends_with('\n') {
+                output.content.push_str("\n\n");
+                if let Some(sink) = sink {
+                    sink.text("\n\n");
+                }
+            }
+        }
+
+        if output.stop_reason.as_deref() == Some("max_tokens") {
+            info!("Claude response truncated by max_tokens");
+        }
+        info!(
+            "Claude usage: {} input, {} output, {} cache write, {} cache read tokens",
+            output.usage.input_tokens,
+            output.usage.output_tokens,
+            output.usage.cache_creation_tokens,
+            output.usage.cache_read_tokens
+        );
+        Ok(output.into_response())
+    }
+
+    async fn send_turn(
+        &self,
+        body: &ClaudeRequest<'_>,
+        output: &mut TurnOutput,
+    ) -> Result<Turn, String> {
+        let response = self.send(body).await?;
+
+        let claude_response = response.json::<ClaudeResponse>().await.map_err(|e| {
+            error!("Failed to parse Claude API response: {}", e);
+            format!("Failed to parse response: {}", e)
+        })?;
+
+        let mut usage = Usage::default();
+        claude_response.usage.apply(&mut usage);
+        output.usage.add(&usage);
+
+        // Server tool blocks (web search) are interleaved with the text blocks
+        for block in claude_response.content.iter().filter_map(Content::parse) {
+            if block.content_type == "thinking" {
+                output.start_thinking(None);
+                output.reasoning.push_str(&block.thinking);
+                continue;
+            }
+            if block.content_type != "text" {
+                continue;
+            }
+            let text = output.after_seam(&block.text);
+            output.content.push_str(text);
+            for citation in block
+                .citations
+                .iter()
+                .flatten()
+                .filter_map(ClaudeCitation::to_citation)
+            {
+                push_citation(&mut output.citations, citation);
+            }
+        }
+
+        Ok(Turn {
+            blocks: claude_response.content,
+            stop_reason: claude_response.stop_reason,
+        })
+    }
+
+    async fn stream_turn(
+        &self,
+        body: &ClaudeRequest<'_>,
+        sink: &StreamSink,
+        output: &mut TurnOutput,
+    ) -> Result<Turn, String> {
+        let response = self.send(body).await?;
+
+        let mut blocks: Vec<serde_json::Value> = Vec::new();
+        let mut partial_json: HashMap<usize, String> = HashMap::new();
+        let mut stop_reason = None;
+        let mut usage = Usage::default();
+        let mut stream_error = None;
+
+        read_sse(response, "Claude", |event| {
+            match serde_json::from_str::<ClaudeStreamEvent>(&event.data) {
+                Ok(ClaudeStreamEvent::ContentBlockStart {
+                    index,
+                    content_block,
+                }) => {
+                    if blocks.len() <= index {
+                        blocks.resize(index + 1, serde_json::Value::Null);
+                    }
+                    if content_block["type"] == "thinking" {
+                        output.start_thinking(Some(sink));
+                    }
+                    // Search results arrive whole in the block start
+                    if content_block["type"] == "web_search_tool_result" {
+                        let id = content_block["tool_use_id"].as_str().unwrap_or_default();
+                        match search_results(&content_block) {
+                            Ok(results) => sink.search_results(id, results, None),
+                            Err(e) => {
+                                error!("Claude web search failed: {}", e);
+                                sink.search_results(id, Vec::new(), Some(e));
+                            }
+                        }
+                    }
+                    blocks[index] = content_block;
+                }
+                Ok(ClaudeStreamEvent::ContentBlockDelta { index, delta }) => match delta {
+                    ClaudeStreamDelta::TextDelta { text } => {
+                        append_to_block(blocks.get_mut(index), "text", &text);
+                        let text = output.after_seam(&text);
+                        if !text.is_empty() {
+                            output.content.push_str(text);
+                            sink.text(text);
+                        }
+                    }
+                    ClaudeStreamDelta::ThinkingDelta { thinking } => {
+                        append_to_block(blocks.get_mut(index), "thinking", &thinking);
+                        if !thinking.is_empty() {
+                            output.reasoning.push_str(&thinking);
+                            sink.reasoning(&thinking);
+                        }
+                    }
+                    ClaudeStreamDelta::SignatureDelta { signature } => {
+                        append_to_block(blocks.get_mut(index), "signature", &signature);
+                    }
+                    ClaudeStreamDelta::CitationsDelta { citation } => {
+                        if let Some(block) = blocks.get_mut(index) {
+                            match block["citations"].as_array_mut() {
+                                Some(citations) => citations.push(citation.clone()),
+                                None => block["citations"] = json!([citation.clone()]),
+                            }
+                        }
+                        let parsed = serde_json::from_value::<ClaudeCitation>(citation).ok();
+                        if let Some(citation) =
+                            parsed.as_ref().and_then(ClaudeCitation::to_citation)
+                        {
+                            match &citation.document {
+                                Some(document) => {
+                                    info!("Received citation of document {}", document.index)
+                                }
+                                None => info!("Received citation: {}", citation.url),
+                            }
+                            sink.citation(&citation);
+                            push_citation(&mut output.citations, citation);
+                        }
+                    }
+                    ClaudeStreamDelta::InputJsonDelta { partial_json: json } => {
+                        partial_json.entry(index).or_default().push_str(&json);
+                    }
+                    ClaudeStreamDelta::Other => {
+                        info!("Received other delta type, ignoring");
+                    }
+                },
+                Ok(ClaudeStreamEvent::ContentBlockStop { index }) => {
+                    if let (Some(json), Some(block)) =
+                        (partial_json.remove(&index), blocks.get_mut(index))
+                    {
+                        // An empty fragment list means the tool takes no input
+                        block["input"] = if json.trim().is_empty() {
+                            json!({})
+                        } else {
+                            serde_json::from_str(&json).unwrap_or_else(|e| {
+                                error!("Failed to parse tool input {}: {}", json, e);
+                                json!({})
+                            })
+                        };
+                    }
+                    if let Some(block) = blocks
+                        .get(index)
+                        .filter(|block| block["type"] == "server_tool_use")
+                    {
+                        sink.searching(
+                            block["id"].as_str().unwrap_or_default(),
+                            block["input"]["query"].as_str().unwrap_or_default(),
+                        );
+                    }
+                }
+                Ok(ClaudeStreamEvent::MessageStart { message }) => {
+                    if let Ok(start) =
+                        serde_json::from_value::<ClaudeUsage>(message["usage"].clone())
+                    {
+                        start.apply(&mut usage);
+                    }
+                }
+                Ok(ClaudeStreamEvent::MessageDelta {
+                    delta,
+                    usage: delta_usage,
+                }) => {
+                    stop_reason = delta["stop_reason"].as_str().map(|r| r.to_string());
+                    delta_usage.apply(&mut usage);
+                }
+                Ok(ClaudeStreamEvent::Ping) => {}
+                // Errors can arrive mid-stream, e.g. overloaded_error
+                Ok(ClaudeStreamEvent::Error { error }) => {
+                    error!("Claude stream error: {}", error);
+                    stream_error = Some(
+                        error
+                            .get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("Unknown error")
+                            .to_string(),
+                    );
+                }
+                Ok(parsed) => {
+                    info!("Claude stream event: {:?}", parsed);
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to parse Claude {} event: {} - Error: {}",
+                        event.event, event.data, e
+                    );
+                }
+            }
+        })
+        .await?;
+
+        if let Some(e) = stream_error {
+            return Err(format!("Claude API error: {}", e));
+        }
+        output.usage.add(&usage);
+
+        Ok(Turn {
+            blocks,
+            stop_reason,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ClaudeProvider {
+    fn id(&self) -> &str {
+        "claude"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            vision: true,
+            web_search: true,
+            citations: true,
+        }
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        let response = self.run(request, None).await?;
+
+        if response.content.is_empty() {
+            error!("Claude response contained no text content");
+            return Err("Empty response from Claude API".to_string());
+        }
+
+        Ok(response)
+    }
+
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String> {
+        let response = self.run(request, Some(sink)).await?;
+
+        info!(
+            "Streaming completed from Claude ({} chars)",
+            response.content.len()
+        );
+
+        Ok(response)
+    }
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        // Get Claude API key from storage
+        let api_key = match crate::get_api_key_file("claude") {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                info!("No Claude API key found");
+                return Ok(vec![]);
+            }
+            Err(e) => {
+                error!("Error getting Claude API key: {}", e);
+                return Ok(vec![]);
+            }
+        };
+
+        let response = reqwest::Client::new()
+            .get(MODELS_URL)
+            .header("x-api-key", &api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .send()
+            .await;
+
+        let resp = match response {
+            Ok(resp) if resp.status().is_success() => resp,
+            Ok(resp) => {
+                let status = resp.status();
+                let error_text = resp.text().await.unwrap_or_default();
+                error!("Claude models API error {}: {}", status, error_text);
+                return Ok(vec![]);
+            }
+            Err(e) => {
+                error!("Failed to connect to Claude API: {}", e);
+                return Ok(vec![]);
+            }
+        };
+
+        let data = match resp.json::<serde_json::Value>().await {
+            Ok(data) => data,
+            Err(e) => {
+                error!("Failed to parse Claude models response: {}", e);
+                return Ok(vec![]);
+            }
+        };
+
+        let Some(models_array) = data.get("data").and_then(|d| d.as_array()) else {
+            error!("Claude API response missing 'data' field");
+            return Ok(vec![]);
+        };
+
+        let models: Vec<serde_json::Value> = models_array
+            .iter()
+            .map(|model| {
+                let id = model
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                serde_json::json!({
+                    "id": id,
+                    "name": model.get("display_name").and_then(|v| v.as_str()).unwrap_or(id),
+                    "description": "Claude API model",
+                    "provider": "claude"
+                })
+            })
+            .collect();
+        info!("Successfully fetched {} Claude models", models.len());
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_citation(value: serde_json::Value) -> Citation {
+        serde_json::from_value::<ClaudeCitation>(value)
+            .unwrap()
+            .to_citation()
+            .unwrap()
+    }
+
+    #[test]
+    fn maps_char_location_citation() {
+        let citation = parse_citation(json!({
+            "type": "char_location",
+            "cited_text": "The grass is green.",
+            "document_index": 0,
+            "document_title": "notes.txt",
+            "start_char_index": 0,
+            "end_char_index": 20
+        }));
+
+        assert_eq!(citation.cited_text.as_deref(), Some("The grass is green."));
+        assert_eq!(
+            citation.document,
+            Some(DocumentCitation {
+                index: 0,
+                title: Some("notes.txt".to_string()),
+                location: DocumentLocation::Char { start: 0, end: 20 },
+            })
+        );
+    }
+
+    #[test]
+    fn maps_page_location_citation() {
+        let citation = parse_citation(json!({
+            "type": "page_location",
+            "cited_text": "Revenue grew 12%.",
+            "document_index": 1,
+            "document_title": null,
+            "start_page_number": 3,
+            "end_page_number": 4
+        }));
+
+        assert_eq!(
+            citation.document,
+            Some(DocumentCitation {
+                index: 1,
+                title: None,
+                location: DocumentLocation::Page { start: 3, end: 4 },
+            })
+        );
+    }
+
+    #[test]
+    fn maps_content_block_location_citation() {
+        let citation = parse_citation(json!({
+            "type": "content_block_location",
+            "cited_text": "Second block.",
+            "document_index": 2,
+            "document_title": "Custom",
+            "start_block_index": 1,
+            "end_block_index": 2
+        }));
+
+        assert_eq!(
+            citation.document.map(|document| document.location),
+            Some(DocumentLocation::ContentBlock { start: 1, end: 2 })
+        );
+    }
+
+    #[test]
+    fn keeps_web_search_citation_url() {
+        let citation = parse_citation(json!({
+            "type": "web_search_result_location",
+            "url": "https://example.com",
+            "title": "Example",
+            "cited_text": "An example.",
+            "encrypted_index": "abc"
+        }));
+
+        assert_eq!(citation.url, "https://example.com");
+        assert_eq!(citation.document, None);
+    }
+
+    #[test]
+    fn prefill_cuts_trailing_whitespace() {
+        let blocks = [json!({ "type": "text", "text": "The answer is \n" })];
+
+        let (prefill, cut) = continuation_prefill(&blocks).unwrap();
+        assert_eq!(
+            prefill,
+            [json!({ "type": "text", "text": "The answer is" })]
+        );
+        assert_eq!(cut, " \n");
+    }
+
+    #[test]
+    fn prefill_skips_empty_last_text_block() {
+        let blocks = [
+            json!({ "type": "text", "text": "First part." }),
+            json!({ "type": "text", "text": "" }),
+        ];
+
+        let (prefill, cut) = continuation_prefill(&blocks).unwrap();
+        assert_eq!(prefill, [json!({ "type": "text", "text": "First part." })]);
+        assert_eq!(cut, "");
+    }
+
+    #[test]
+    fn prefill_needs_text_at_the_end() {
+        let blocks = [
+            json!({ "type": "text", "text": "Let me search." }),
+            json!({ "type": "server_tool_use", "id": "srvtoolu_1", "name": "web_search", "input": {} }),
+        ];
+
+        assert_eq!(continuation_prefill(&blocks), None);
+    }
+
+    #[test]
+    fn continued_text_drops_repeated_seam() {
+        let mut output = TurnOutput {
+            seam: " \n".to_string(),
+            ..Default::default()
+        };
+
+        // Spread over stream deltas
+        assert_eq!(output.after_seam(" "), "");
+        assert_eq!(output.after_seam("\n\nbar"), "\nbar");
+        assert_eq!(output.after_seam(" baz"), " baz");
+    }
+
+    #[test]
+    fn cache_breakpoint_marks_last_block() {
+        let messages = [
+            json!({ "role": "user", "content": "Hi" }),
+            json!({ "role": "assistant", "content": "Hello" }),
+            json!({ "role": "user", "content": "Tell me more" }),
+        ];
+
+        let cached = with_cache_breakpoint(&messages);
+        assert_eq!(cached[..2], messages[..2]);
+        assert_eq!(
+            cached[2]["content"],
+            json!([{ "type": "text", "text": "Tell me more", "cache_control": { "type": "ephemeral" } }])
+        );
+    }
+}
@@ -0,0 +1,476 @@
+// Google Gemini API
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    check_response, map_send_error, read_sse, stop_reason, ChatRequest, ChatResponse, ContentBlock,
+    LlmProvider, Message, MessageContent, ProviderCapabilities, StreamSink, Usage,
+    MODEL_LIST_CLIENT,
+};
+
+pub(crate) const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    generation_config: GeminiGenerationConfig,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_config: Option<GeminiThinkingConfig>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiThinkingConfig {
+    // Unset lets the model pick its budget per request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_budget: Option<u32>,
+    include_thoughts: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct GeminiContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<GeminiInlineData>,
+    // Marks thought summaries among the response parts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thought: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiInlineData {
+    mime_type: String,
+    data: String,
+}
+
+impl From<&Message> for GeminiContent {
+    fn from(message: &Message) -> Self {
+        let parts = match &message.content {
+            MessageContent::Text(text) => vec![GeminiPart::text(text)],
+            MessageContent::Multimodal(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Image { source } => {
+                        Some(GeminiPart::inline(&source.media_type, &source.data))
+                    }
+                    // Gemini reads PDFs natively
+                    ContentBlock::Document { source, .. } if source.is_pdf() => {
+                        Some(GeminiPart::inline(&source.media_type, &source.data))
+                    }
+                    _ => block.as_text().map(|text| GeminiPart::text(&text)),
+                })
+                .collect(),
+        };
+
+        // Gemini calls the assistant "model"
+        let role = match message.role.as_str() {
+            "assistant" => "model",
+            _ => "user",
+        };
+
+        Self {
+            role: Some(role.to_string()),
+            parts,
+        }
+    }
+}
+
+impl GeminiPart {
+    fn text(text: &str) -> Self {
+        Self {
+            text: Some(text.to_string()),
+            inline_data: None,
+            thought: None,
+        }
+    }
+
+    fn inline(mime_type: &str, data: &str) -> Self {
+        Self {
+            text: None,
+            inline_data: Some(GeminiInlineData {
+                mime_type: mime_type.to_string(),
+                data: data.to_string(),
+            }),
+            thought: None,
+        }
+    }
+}
+
+// Same shape for the full response and each streamed chunk
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+    // Cumulative when streaming
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    // Thinking models bill their reasoning as output
+    #[serde(default)]
+    thoughts_token_count: u64,
+    #[serde(default)]
+    cached_content_token_count: u64,
+}
+
+impl From<&GeminiUsage> for Usage {
+    fn from(usage: &GeminiUsage) -> Self {
+        Usage {
+            input_tokens: usage
+                .prompt_token_count
+                .saturating_sub(usage.cached_content_token_count),
+            output_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+            cache_creation_tokens: 0,
+            cache_read_tokens: usage.cached_content_token_count,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    #[serde(default)]
+    content: GeminiContent,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+impl GeminiResponse {
+    fn text(&self) -> String {
+        self.parts_text(false)
+    }
+
+    fn thoughts(&self) -> String {
+        self.parts_text(true)
+    }
+
+    fn parts_text(&self, thought: bool) -> String {
+        self.candidates
+            .first()
+            .map(|candidate| {
+                candidate
+                    .content
+                    .parts
+                    .iter()
+                    .filter(|part| part.thought.unwrap_or(false) == thought)
+                    .filter_map(|part| part.text.as_deref())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn block_reason(&self) -> Option<&str> {
+        self.prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.as_deref())
+    }
+}
+
+#[derive(Default)]
+pub struct GeminiProvider;
+
+impl GeminiProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Stored key, or GEMINI_API_KEY migrated from the environment.
+    fn api_key(&self) -> Result<String, String> {
+        crate::load_api_key("gemini")
+    }
+
+    fn build_request(&self, request: &ChatRequest) -> GeminiRequest {
+        let options = &request.options;
+
+        // System prompts go in their own field rather than the turn list
+        GeminiRequest {
+            contents: request.turns().map(GeminiContent::from).collect(),
+            system_instruction: request.system_prompt().map(|system| GeminiContent {
+                role: None,
+                parts: vec![GeminiPart::text(&system)],
+            }),
+            generation_config: GeminiGenerationConfig {
+                max_output_tokens: options.max_tokens,
+                temperature: options.temperature,
+                top_p: options.top_p,
+                top_k: options.top_k,
+                stop_sequences: options.stop.clone(),
+                seed: options.seed,
+                thinking_config: options
+                    .reasoning
+                    .as_ref()
+                    .map(|reasoning| GeminiThinkingConfig {
+                        thinking_budget: reasoning.budget_tokens,
+                        include_thoughts: true,
+                    }),
+            },
+        }
+    }
+
+    async fn send(&self, request: &ChatRequest, stream: bool) -> Result<reqwest::Response, String> {
+        let api_key = self.api_key()?;
+        let url = if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse",
+                GEMINI_BASE_URL, request.model
+            )
+        } else {
+            format!(
+                "{}/models/{}:generateContent",
+                GEMINI_BASE_URL, request.model
+            )
+        };
+
+        info!(
+            "Sending request to Gemini API with model: {}",
+            request.model
+        );
+        let response = reqwest::Client::new()
+            .post(url)
+            .header("x-goog-api-key", api_key)
+            .json(&self.build_request(request))
+            .send()
+            .await
+            .map_err(|e| map_send_error(e, "Gemini"))?;
+
+        check_response(response, "Gemini").await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            vision: true,
+            web_search: false,
+            citations: false,
+        }
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        let response = self.send(request, false).await?;
+
+        let parsed = response.json::<GeminiResponse>().await.map_err(|e| {
+            error!("Failed to parse Gemini API response: {}", e);
+            format!("Failed to parse response: {}", e)
+        })?;
+
+        if let Some(reason) = parsed.block_reason() {
+            return Err(format!("Gemini blocked the prompt: {}", reason));
+        }
+
+        let content = parsed.text();
+        if content.is_empty() {
+            let reason = parsed
+                .candidates
+                .first()
+                .and_then(|c| c.finish_reason.as_deref())
+                .unwrap_or("unknown");
+            error!(
+                "Gemini response contained no text (finish reason: {})",
+                reason
+            );
+            return Err(format!("Empty response from Gemini API ({})", reason));
+        }
+
+        let thoughts = parsed.thoughts();
+        Ok(ChatResponse {
+            content,
+            citations: None,
+            usage: parsed.usage_metadata.as_ref().map(Usage::from),
+            stop_reason: parsed
+                .candidates
+                .first()
+                .and_then(|c| c.finish_reason.as_deref())
+                .map(stop_reason),
+            search: None,
+            reasoning: (!thoughts.is_empty()).then_some(thoughts),
+        })
+    }
+
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String> {
+        let response = self.send(request, true).await?;
+        let mut full_response = String::new();
+        let mut reasoning = String::new();
+        let mut block_reason = None;
+        let mut usage = None;
+        let mut finish_reason = None;
+
+        read_sse(response, "Gemini", |event| {
+            match serde_json::from_str::<GeminiResponse>(&event.data) {
+                Ok(chunk) => {
+                    if let Some(reason) = chunk.block_reason() {
+                        block_reason = Some(reason.to_string());
+                    }
+                    if let Some(chunk_usage) = &chunk.usage_metadata {
+                        usage = Some(Usage::from(chunk_usage));
+                    }
+                    if let Some(reason) = chunk
+                        .candidates
+                        .first()
+                        .and_then(|c| c.finish_reason.as_deref())
+                    {
+                        finish_reason = Some(stop_reason(reason));
+                    }
+                    let thoughts = chunk.thoughts();
+                    if !thoughts.is_empty() {
+                        reasoning.push_str(&thoughts);
+                        sink.reasoning(&thoughts);
+                    }
+                    let text = chunk.text();
+                    if !text.is_empty() {
+                        full_response.push_str(&text);
+                        sink.text(&text);
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to parse JSON from Gemini chunk: {} - Error: {}",
+                        event.data, e
+                    );
+                }
+            }
+        })
+        .await?;
+
+        if let Some(reason) = block_reason {
+            return Err(format!("Gemini blocked the prompt: {}", reason));
+        }
+
+        info!(
+            "Streaming completed from Gemini ({} chars)",
+            full_response.len()
+        );
+
+        Ok(ChatResponse {
+            content: full_response,
+            citations: None,
+            usage,
+            stop_reason: finish_reason,
+            search: None,
+            reasoning: (!reasoning.is_empty()).then_some(reasoning),
+        })
+    }
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        let api_key = match self.api_key() {
+            Ok(key) => key,
+            Err(_) => {
+                info!("No Gemini API key found");
+                return Ok(vec![]);
+            }
+        };
+
+        let response = MODEL_LIST_CLIENT
+            .get(format!("{}/models?pageSize=100", GEMINI_BASE_URL))
+            .header("x-goog-api-key", api_key)
+            .send()
+            .await;
+
+        let resp = match response {
+            Ok(resp) if resp.status().is_success() => resp,
+            Ok(resp) => {
+                let status = resp.status();
+                let error_text = resp.text().await.unwrap_or_default();
+                error!("Gemini models API error {}: {}", status, error_text);
+                return Ok(vec![]);
+            }
+            Err(e) => {
+                error!("Failed to connect to Gemini API: {}", e);
+                return Ok(vec![]);
+            }
+        };
+
+        let data = match resp.json::<serde_json::Value>().await {
+            Ok(data) => data,
+            Err(e) => {
+                error!("Failed to parse Gemini models response: {}", e);
+                return Ok(vec![]);
+            }
+        };
+
+        let Some(models_array) = data.get("models").and_then(|m| m.as_array()) else {
+            error!("Gemini API response missing 'models' field");
+            return Ok(vec![]);
+        };
+
+        // Skip embedding and other models that can't chat
+        let models: Vec<serde_json::Value> = models_array
+            .iter()
+            .filter(|model| {
+                model
+                    .get("supportedGenerationMethods")
+                    .and_then(|m| m.as_array())
+                    .is_some_and(|methods| methods.iter().any(|m| m == "generateContent"))
+            })
+            .filter_map(|model| {
+                let name = model.get("name").and_then(|v| v.as_str())?;
+                let id = name.strip_prefix("models/").unwrap_or(name);
+                Some(serde_json::json!({
+                    "id": id,
+                    "name": model.get("displayName").and_then(|v| v.as_str()).unwrap_or(id),
+                    "description": model.get("description").and_then(|v| v.as_str()).unwrap_or("Gemini API model"),
+                    "provider": "gemini"
+                }))
+            })
+            .collect();
+
+        info!("Successfully fetched {} Gemini models", models.len());
+        Ok(models)
+    }
+}
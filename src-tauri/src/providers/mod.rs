@@ -0,0 +1,1048 @@
+// Chat provider abstraction
+//
+// Every chat backend implements `LlmProvider`, and the `chat` / `stream_chat`
+// commands dispatch to it by provider id. Adding a backend means adding a
+// module here and registering it in `get_provider`.
+
+use async_trait::async_trait;
+use futures_util::future::{AbortHandle, Abortable};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tauri::ipc::Channel;
+
+use crate::endpoints;
+use crate::profiles::ProfileStore;
+use crate::tools::ToolOutput;
+use crate::usage;
+
+mod claude;
+mod gemini;
+mod ollama;
+mod openai;
+mod perplexity;
+mod sse;
+mod think;
+
+pub use claude::ClaudeProvider;
+pub use gemini::GeminiProvider;
+pub(crate) use gemini::GEMINI_BASE_URL;
+pub use ollama::OllamaProvider;
+pub use openai::OpenAiProvider;
+pub use perplexity::PerplexityProvider;
+use sse::{SseDecoder, SseEvent};
+
+/// Client for model list requests. A server that doesn't answer within a few
+/// seconds is left out instead of holding up the model picker.
+pub(crate) static MODEL_LIST_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(2))
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap_or_default()
+});
+
+// Message types shared by all providers. The wire format follows the Claude
+// Messages API, which is what the frontend already builds.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Multimodal(Vec<ContentBlock>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum ContentBlock {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image")]
+    Image { source: ImageSource },
+    #[serde(rename = "document")]
+    Document {
+        source: DocumentSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<DocumentCitations>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImageSource {
+    #[serde(rename = "type")]
+    pub source_type: String,
+    pub media_type: String,
+    pub data: String,
+}
+
+/// A PDF (`type: "base64"`, `application/pdf`) or plain text (`type: "text"`,
+/// `text/plain`) document.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentSource {
+    #[serde(rename = "type")]
+    pub source_type: String,
+    pub media_type: String,
+    pub data: String,
+}
+
+impl DocumentSource {
+    pub fn is_pdf(&self) -> bool {
+        self.media_type == "application/pdf"
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentCitations {
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Message {
+    pub role: String,
+    pub content: MessageContent,
+}
+
+impl Message {
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: MessageContent::Text(text.into()),
+        }
+    }
+}
+
+impl MessageContent {
+    /// Concatenated text of all text blocks, for providers without multimodal input.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Multimodal(blocks) => blocks
+                .iter()
+                .filter_map(ContentBlock::as_text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl ContentBlock {
+    /// The block as prompt text. Plain text documents are inlined, wrapped
+    /// in a `<document>` tag; images and PDFs have no text form.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            ContentBlock::Text { text } => Some(text.clone()),
+            ContentBlock::Document { source, title, .. } if !source.is_pdf() => Some(match title {
+                Some(title) => format!(
+                    "<document title=\"{}\">\n{}\n</document>",
+                    title, source.data
+                ),
+                None => format!("<document>\n{}\n</document>", source.data),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// What a provider supports, so the frontend can enable features per backend.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ProviderCapabilities {
+    pub streaming: bool,
+    pub vision: bool,
+    pub web_search: bool,
+    pub citations: bool,
+}
+
+/// Per-request generation settings. Unset fields fall back to each
+/// provider's defaults; settings a provider doesn't support are not sent.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GenerationOptions {
+    pub system: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub stop: Vec<String>,
+    pub seed: Option<i64>,
+    /// Claude prompt caching of the system prompt and conversation history;
+    /// on unless set to false.
+    pub prompt_cache: Option<bool>,
+    /// Claude: offer the app's own tools (calendar, weather, ...); off unless
+    /// set, as their schemas are billed as input on every turn.
+    pub client_tools: bool,
+    /// Claude's server-side web search; only offered to the model when set.
+    pub web_search: Option<WebSearchOptions>,
+    /// Claude: when the answer hits `max_tokens`, continue it in follow-up
+    /// requests and stream the result as one answer.
+    pub auto_continue: bool,
+    /// Perplexity search settings; Perplexity's defaults apply when unset.
+    pub perplexity_search: Option<PerplexitySearchOptions>,
+    /// Extended thinking / reasoning; off unless set. Models that always
+    /// reason (Perplexity sonar-reasoning, DeepSeek R1) report it regardless.
+    pub reasoning: Option<ReasoningOptions>,
+    /// Ollama: context window in tokens; Ollama's default is far below what
+    /// most models are trained for.
+    pub num_ctx: Option<u32>,
+    /// Ollama: CPU threads; Ollama picks one per physical core when unset.
+    pub num_thread: Option<u32>,
+    /// Ollama: penalty for repeating tokens, 1.0 for none.
+    pub repeat_penalty: Option<f32>,
+}
+
+/// How much a model may think before answering. Each provider takes the
+/// setting it understands: Claude and Gemini a token budget, OpenAI and
+/// Ollama an effort level (Ollama falls back to plain on/off).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ReasoningOptions {
+    pub budget_tokens: Option<u32>,
+    /// "low", "medium" or "high"
+    pub effort: Option<String>,
+}
+
+/// Limits for Claude's web search tool. Allowed and blocked domains can't be
+/// combined.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct WebSearchOptions {
+    pub max_uses: Option<u32>,
+    pub allowed_domains: Vec<String>,
+    pub blocked_domains: Vec<String>,
+    pub user_location: Option<UserLocation>,
+}
+
+/// Approximate location used to localize search results.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct UserLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Two letter ISO country code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// IANA time zone, e.g. "America/New_York"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Search settings for Perplexity's Sonar models. A domain filter entry
+/// prefixed with `-` excludes that domain.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PerplexitySearchOptions {
+    /// "hour", "day", "week", "month" or "year"
+    pub search_recency_filter: Option<String>,
+    pub search_domain_filter: Vec<String>,
+    /// "web", "academic" or "sec"
+    pub search_mode: Option<String>,
+    pub return_images: Option<bool>,
+    pub return_related_questions: Option<bool>,
+    /// "low", "medium" or "high"
+    pub search_context_size: Option<String>,
+}
+
+/// A single chat turn to send to a provider.
+#[derive(Clone, Debug)]
+pub struct ChatRequest {
+    /// Provider id, e.g. "ollama" or "endpoint:lm-studio"
+    pub provider: String,
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub options: GenerationOptions,
+    /// Groups turns of one conversation in the usage ledger.
+    pub conversation_id: Option<String>,
+}
+
+impl ChatRequest {
+    /// Builds a request from the command arguments, falling back to a single
+    /// user message when no history is provided.
+    pub fn new(provider: &str, model: String, prompt: String, messages: Vec<Message>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model,
+            messages: if messages.is_empty() {
+                vec![Message::user(prompt)]
+            } else {
+                messages
+            },
+            options: GenerationOptions::default(),
+            conversation_id: None,
+        }
+    }
+
+    /// Sets the request's options; what they leave unset comes from the
+    /// model's saved profile.
+    pub fn with_options(
+        mut self,
+        options: Option<GenerationOptions>,
+        profiles: &ProfileStore,
+    ) -> Self {
+        self.options = options.unwrap_or_default();
+        if let Some(profile) = profiles.get(&self.provider, &self.model) {
+            profile.apply(&mut self.options);
+        }
+        self
+    }
+
+    pub fn with_conversation(mut self, conversation_id: Option<String>) -> Self {
+        self.conversation_id = conversation_id;
+        self
+    }
+
+    /// Adds the turn's token usage, when the provider reported it, to the
+    /// usage ledger.
+    pub fn record_usage(&self, provider: &str, response: &ChatResponse) {
+        if let Some(usage) = &response.usage {
+            usage::record(
+                provider,
+                &self.model,
+                self.conversation_id.as_deref(),
+                usage,
+                false,
+            );
+        }
+    }
+
+    /// Records an estimate for a stream cancelled after `output_chars` of
+    /// text and reasoning, at four characters per token. Tool rounds resend
+    /// the history, so multi-round turns are undercounted.
+    pub fn record_estimated_usage(&self, provider: &str, output_chars: usize) {
+        let prompt_chars: usize = self
+            .messages_with_system()
+            .iter()
+            .filter_map(|message| serde_json::to_string(message).ok())
+            .map(|json| json.len())
+            .sum();
+        let usage = Usage {
+            input_tokens: (prompt_chars / 4) as u64,
+            output_tokens: output_chars.div_ceil(4) as u64,
+            ..Default::default()
+        };
+        usage::record(
+            provider,
+            &self.model,
+            self.conversation_id.as_deref(),
+            &usage,
+            true,
+        );
+    }
+
+    /// The system prompt from the options followed by any `system` messages
+    /// in the history.
+    pub fn system_prompt(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .options
+            .system
+            .iter()
+            .filter(|system| !system.trim().is_empty())
+            .cloned()
+            .chain(
+                self.messages
+                    .iter()
+                    .filter(|message| message.role == "system")
+                    .map(|message| message.content.text()),
+            )
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    /// The conversation without `system` messages, for APIs that take the
+    /// system prompt separately.
+    pub fn turns(&self) -> impl Iterator<Item = &Message> {
+        self.messages
+            .iter()
+            .filter(|message| message.role != "system")
+    }
+
+    /// The conversation with the system prompt as a leading `system` message,
+    /// for chat completion style APIs.
+    pub fn messages_with_system(&self) -> Vec<Message> {
+        self.system_prompt()
+            .map(|system| Message {
+                role: "system".to_string(),
+                content: MessageContent::Text(system),
+            })
+            .into_iter()
+            .chain(self.turns().cloned())
+            .collect()
+    }
+}
+
+/// A source the answer was grounded on: a web search result or search hit,
+/// or a passage of a document attached to the chat (`url` is then empty).
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Citation {
+    pub url: String,
+    pub title: Option<String>,
+    pub cited_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<DocumentCitation>,
+}
+
+/// The attached document a citation quotes, and where.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DocumentCitation {
+    /// Position among the documents of the request
+    pub index: usize,
+    pub title: Option<String>,
+    pub location: DocumentLocation,
+}
+
+/// Cited range, end exclusive: characters of a text document, pages of a
+/// PDF (from 1) or blocks of a custom content document.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentLocation {
+    Char { start: u64, end: u64 },
+    Page { start: u64, end: u64 },
+    ContentBlock { start: u64, end: u64 },
+}
+
+impl Citation {
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// `usage` object of chat completion style APIs (OpenAI, Perplexity).
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct CompletionUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: u64,
+}
+
+impl From<CompletionUsage> for Usage {
+    fn from(usage: CompletionUsage) -> Self {
+        // Cached tokens are counted in prompt_tokens too
+        let cached = usage
+            .prompt_tokens_details
+            .map(|details| details.cached_tokens)
+            .unwrap_or_default();
+        Usage {
+            input_tokens: usage.prompt_tokens.saturating_sub(cached),
+            output_tokens: usage.completion_tokens,
+            cache_creation_tokens: 0,
+            cache_read_tokens: cached,
+        }
+    }
+}
+
+/// Maps a chat completion, Gemini or Ollama finish reason onto Claude's
+/// stop reasons, so callers can check for `max_tokens` everywhere.
+pub(crate) fn stop_reason(finish_reason: &str) -> String {
+    match finish_reason.to_lowercase().as_str() {
+        "stop" => "end_turn".to_string(),
+        "length" | "max_tokens" => "max_tokens".to_string(),
+        "tool_calls" => "tool_use".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Adds `citation` unless its URL, or for documents the same passage, is
+/// already listed.
+pub fn push_citation(citations: &mut Vec<Citation>, citation: Citation) -> bool {
+    if citations.iter().any(|existing| match &citation.document {
+        Some(document) => existing.document.as_ref() == Some(document),
+        None => existing.url == citation.url,
+    }) {
+        return false;
+    }
+    citations.push(citation);
+    true
+}
+
+/// Token counts reported for a chat turn. `input_tokens` excludes prompt
+/// tokens written to or read from the provider's cache.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+    }
+}
+
+/// Final result of a chat turn, also used as the stream completion payload.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ChatResponse {
+    pub content: String,
+    pub citations: Option<Vec<Citation>>,
+    pub usage: Option<Usage>,
+    /// Why generation ended, in Claude's terms (`end_turn`, `max_tokens`,
+    /// `stop_sequence`, ...), for providers that report it.
+    pub stop_reason: Option<String>,
+    /// Search results, images and follow-up questions, for providers that
+    /// return them alongside the answer.
+    pub search: Option<SearchDetails>,
+    /// The model's reasoning, kept apart from `content`.
+    pub reasoning: Option<String>,
+}
+
+/// Extra search output returned with an answer (Perplexity).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchDetails {
+    pub results: Vec<SearchResult>,
+    pub images: Vec<SearchImage>,
+    pub related_questions: Vec<String>,
+}
+
+impl SearchDetails {
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty() && self.images.is_empty() && self.related_questions.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchResult {
+    pub url: String,
+    pub title: Option<String>,
+    /// Publication date, when known
+    pub date: Option<String>,
+    pub last_updated: Option<String>,
+    pub snippet: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchImage {
+    pub image_url: String,
+    pub origin_url: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Messages sent over a request's stream channel, tagged as
+/// `{ event: "delta", data: { text } }` and so on.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// First message, carrying the id `cancel_stream` takes.
+    Started {
+        request_id: u64,
+    },
+    Delta {
+        text: String,
+    },
+    /// Reasoning text, streamed apart from the answer.
+    Reasoning {
+        text: String,
+    },
+    /// A source cited while the answer streams; `Done` repeats the
+    /// deduplicated list.
+    Citation(Citation),
+    /// The model asked for a client-side tool; `ToolResult` follows once it ran.
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        id: String,
+        name: String,
+        content: String,
+        is_error: bool,
+    },
+    /// The provider started a web search on its side.
+    Searching {
+        id: String,
+        query: String,
+    },
+    /// Results of a `Searching` search, or the reason it failed.
+    SearchResults {
+        id: String,
+        results: Vec<Citation>,
+        error: Option<String>,
+    },
+    Done(ChatResponse),
+    /// Sent instead of `Done` when the stream is cancelled.
+    Cancelled {
+        request_id: u64,
+        content: String,
+    },
+}
+
+/// Forwards streamed text to the channel of the request that started it, so
+/// concurrent conversations and windows never see each other's tokens.
+pub struct StreamSink {
+    channel: Channel<StreamEvent>,
+    // Text sent so far, kept so a cancelled stream can still report it
+    partial: Mutex<String>,
+    // Length of the reasoning sent so far, for the usage estimate
+    reasoning_chars: AtomicUsize,
+}
+
+impl StreamSink {
+    pub fn new(channel: Channel<StreamEvent>) -> Self {
+        Self {
+            channel,
+            partial: Mutex::new(String::new()),
+            reasoning_chars: AtomicUsize::new(0),
+        }
+    }
+
+    fn send(&self, event: StreamEvent) {
+        if let Err(e) = self.channel.send(event) {
+            error!("Failed to send stream event: {}", e);
+        }
+    }
+
+    pub fn text(&self, text: &str) {
+        if let Ok(mut partial) = self.partial.lock() {
+            partial.push_str(text);
+        }
+        self.send(StreamEvent::Delta {
+            text: text.to_string(),
+        });
+    }
+
+    pub fn reasoning(&self, text: &str) {
+        self.reasoning_chars
+            .fetch_add(text.len(), Ordering::Relaxed);
+        self.send(StreamEvent::Reasoning {
+            text: text.to_string(),
+        });
+    }
+
+    pub fn citation(&self, citation: &Citation) {
+        self.send(StreamEvent::Citation(citation.clone()));
+    }
+
+    pub fn tool_use(&self, id: &str, name: &str, input: &serde_json::Value) {
+        self.send(StreamEvent::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: input.clone(),
+        });
+    }
+
+    pub fn tool_result(&self, id: &str, name: &str, output: &ToolOutput) {
+        self.send(StreamEvent::ToolResult {
+            id: id.to_string(),
+            name: name.to_string(),
+            content: output.content.clone(),
+            is_error: output.is_error,
+        });
+    }
+
+    pub fn searching(&self, id: &str, query: &str) {
+        self.send(StreamEvent::Searching {
+            id: id.to_string(),
+            query: query.to_string(),
+        });
+    }
+
+    pub fn search_results(&self, id: &str, results: Vec<Citation>, error: Option<String>) {
+        self.send(StreamEvent::SearchResults {
+            id: id.to_string(),
+            results,
+            error,
+        });
+    }
+
+    pub fn partial_text(&self) -> String {
+        self.partial
+            .lock()
+            .map(|partial| partial.clone())
+            .unwrap_or_default()
+    }
+
+    /// Characters of text and reasoning sent so far.
+    fn output_chars(&self) -> usize {
+        let text = self.partial.lock().map(|p| p.len()).unwrap_or_default();
+        text + self.reasoning_chars.load(Ordering::Relaxed)
+    }
+}
+
+/// Abort handles for in-flight streams, keyed by request id.
+#[derive(Default)]
+pub struct StreamRegistry {
+    next_id: AtomicU64,
+    streams: Mutex<HashMap<u64, (String, AbortHandle)>>,
+}
+
+impl StreamRegistry {
+    /// Runs `task` under a new request id so `cancel` can abort it. The id
+    /// is passed to `started` before the task begins. Returns the id and
+    /// the task's output, or `None` if it was cancelled.
+    pub async fn track<F: Future>(
+        &self,
+        owner: &str,
+        started: impl FnOnce(u64),
+        task: F,
+    ) -> (u64, Option<F::Output>) {
+        let request_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.insert(request_id, (owner.to_string(), abort_handle));
+        }
+        started(request_id);
+
+        let result = Abortable::new(task, abort_registration).await;
+
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.remove(&request_id);
+        }
+        (request_id, result.ok())
+    }
+
+    /// Runs `stream_chat` under a new request id so it can be cancelled,
+    /// finishing the channel with `Done` or `Cancelled`. Returns the id.
+    pub async fn run(
+        &self,
+        provider: &dyn LlmProvider,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<u64, String> {
+        let (request_id, result) = self
+            .track(
+                provider.id(),
+                |request_id| sink.send(StreamEvent::Started { request_id }),
+                provider.stream_chat(request, sink),
+            )
+            .await;
+
+        match result {
+            Some(response) => {
+                let response = response?;
+                request.record_usage(provider.id(), &response);
+                sink.send(StreamEvent::Done(response));
+            }
+            None => {
+                // Whatever was generated so far is billed all the same
+                info!("Stream {} cancelled", request_id);
+                let output_chars = sink.output_chars();
+                if output_chars > 0 {
+                    request.record_estimated_usage(provider.id(), output_chars);
+                }
+                sink.send(StreamEvent::Cancelled {
+                    request_id,
+                    content: sink.partial_text(),
+                });
+            }
+        }
+        Ok(request_id)
+    }
+
+    /// Aborts a stream, dropping its HTTP connection. Returns false if it
+    /// already finished.
+    pub fn cancel(&self, request_id: u64) -> bool {
+        let stream = match self.streams.lock() {
+            Ok(mut streams) => streams.remove(&request_id),
+            Err(_) => None,
+        };
+
+        match stream {
+            Some((_, abort_handle)) => {
+                abort_handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Aborts every in-flight stream of one provider.
+    pub fn cancel_provider(&self, provider: &str) -> usize {
+        let handles: Vec<AbortHandle> = match self.streams.lock() {
+            Ok(mut streams) => {
+                let ids: Vec<u64> = streams
+                    .iter()
+                    .filter(|(_, (id, _))| id == provider)
+                    .map(|(request_id, _)| *request_id)
+                    .collect();
+                ids.iter()
+                    .filter_map(|request_id| streams.remove(request_id))
+                    .map(|(_, abort_handle)| abort_handle)
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        };
+
+        for abort_handle in &handles {
+            abort_handle.abort();
+        }
+        handles.len()
+    }
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn id(&self) -> &str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String>;
+
+    /// Streams text deltas into `sink` and returns the assembled response.
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String>;
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String>;
+
+    /// `chat`, with the turn added to the usage ledger.
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        let response = self.chat(request).await?;
+        request.record_usage(self.id(), &response);
+        Ok(response)
+    }
+}
+
+pub const PROVIDER_IDS: [&str; 5] = ["claude", "perplexity", "ollama", "openai", "gemini"];
+
+pub fn get_provider(app: &tauri::AppHandle, id: &str) -> Result<Box<dyn LlmProvider>, String> {
+    match id {
+        "claude" => Ok(Box::new(ClaudeProvider::new(app.clone()))),
+        "perplexity" => Ok(Box::new(PerplexityProvider::new(app.clone()))),
+        "ollama" => Ok(Box::new(OllamaProvider::new(app.clone()))),
+        "openai" => Ok(Box::new(OpenAiProvider::openai())),
+        "gemini" => Ok(Box::new(GeminiProvider::new())),
+        _ if id.starts_with(endpoints::PROVIDER_PREFIX) => match endpoints::find_endpoint(id) {
+            Some(endpoint) => Ok(Box::new(endpoint.provider())),
+            None => Err(format!("Endpoint not found: {}", id)),
+        },
+        _ => Err(format!("Unsupported provider: {}", id)),
+    }
+}
+
+// Shared HTTP helpers
+
+/// Turns a non-success response into the user-facing error message.
+pub(crate) async fn check_response(
+    resp: reqwest::Response,
+    label: &str,
+) -> Result<reqwest::Response, String> {
+    if resp.status().is_success() {
+        info!(
+            "Received response from {} API with status: {}",
+            label,
+            resp.status()
+        );
+        return Ok(resp);
+    }
+
+    let status = resp.status();
+    let error_text = resp
+        .text()
+        .await
+        .unwrap_or_else(|_| "Could not read error response".to_string());
+    error!(
+        "{} API request failed with status {}: {}",
+        label, status, error_text
+    );
+
+    if status == 401 {
+        Err(format!(
+            "Authentication failed. Please check your {} API key in Settings.",
+            label
+        ))
+    } else if status == 429 {
+        Err("Rate limit exceeded. Please try again later.".to_string())
+    } else {
+        Err(format!("{} API error ({}): {}", label, status, error_text))
+    }
+}
+
+pub(crate) fn map_send_error(e: reqwest::Error, label: &str) -> String {
+    error!("Failed to connect to {} API: {}", label, e);
+    if e.is_timeout() {
+        "Request timed out. Please check your internet connection.".to_string()
+    } else if e.is_connect() {
+        format!(
+            "Could not connect to {} API. Please check your internet connection.",
+            label
+        )
+    } else {
+        format!("Network error: {}", e)
+    }
+}
+
+/// Reads a Server-Sent Events stream and hands each event to `on_event`.
+/// The `[DONE]` sentinel that OpenAI-style APIs send last is skipped.
+pub(crate) async fn read_sse<F>(
+    response: reqwest::Response,
+    label: &str,
+    mut on_event: F,
+) -> Result<(), String>
+where
+    F: FnMut(&SseEvent),
+{
+    use futures_util::stream::StreamExt;
+
+    let mut stream = response.bytes_stream();
+    let mut decoder = SseDecoder::new();
+
+    while let Some(item) = stream.next().await {
+        let bytes = item.map_err(|e| {
+            error!("Error reading from {} stream: {}", label, e);
+            format!("Error reading from stream: {}", e)
+        })?;
+
+        for event in decoder.feed(&bytes) {
+            if event.data != "[DONE]" {
+                on_event(&event);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a newline-delimited JSON stream (Ollama) and hands each line to `on_line`.
+pub(crate) async fn read_ndjson<F>(
+    response: reqwest::Response,
+    label: &str,
+    mut on_line: F,
+) -> Result<(), String>
+where
+    F: FnMut(&str),
+{
+    use futures_util::stream::StreamExt;
+
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+
+    while let Some(item) = stream.next().await {
+        let bytes = item.map_err(|e| {
+            error!("Error reading from {} stream: {}", label, e);
+            format!("Error reading from stream: {}", e)
+        })?;
+        buffer.extend_from_slice(&bytes);
+
+        // Split on raw bytes so multi-byte characters never straddle a decode
+        while let Some(line_end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=line_end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if !line.trim().is_empty() {
+                on_line(line.trim());
+            }
+        }
+    }
+
+    let rest = String::from_utf8_lossy(&buffer);
+    if !rest.trim().is_empty() {
+        on_line(rest.trim());
+    }
+
+    Ok(())
+}
+
+// Commands
+
+#[tauri::command]
+pub async fn get_chat_providers(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
+    let endpoint_ids = endpoints::load_endpoints()
+        .iter()
+        .map(|endpoint| endpoint.provider_id())
+        .collect::<Vec<_>>();
+
+    PROVIDER_IDS
+        .iter()
+        .copied()
+        .chain(endpoint_ids.iter().map(String::as_str))
+        .map(|id| {
+            let provider = get_provider(&app, id)?;
+            Ok(serde_json::json!({
+                "id": provider.id(),
+                "capabilities": provider.capabilities()
+            }))
+        })
+        .collect()
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn chat(
+    app: tauri::AppHandle,
+    profiles: tauri::State<'_, ProfileStore>,
+    provider: String,
+    model: String,
+    prompt: String,
+    messages: Vec<Message>,
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<ChatResponse, String> {
+    info!(
+        "Starting chat with provider {} and model {}",
+        provider, model
+    );
+    let backend = get_provider(&app, &provider)?;
+    let request = ChatRequest::new(&provider, model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    backend.complete(&request).await
+}
+
+/// Streams over `on_event`; the request id comes first in a `Started`
+/// message so the frontend can cancel while text is still arriving.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_chat(
+    app: tauri::AppHandle,
+    streams: tauri::State<'_, StreamRegistry>,
+    profiles: tauri::State<'_, ProfileStore>,
+    on_event: Channel<StreamEvent>,
+    provider: String,
+    model: String,
+    prompt: String,
+    messages: Vec<Message>,
+    options: Option<GenerationOptions>,
+    conversation_id: Option<String>,
+) -> Result<u64, String> {
+    info!(
+        "Starting stream_chat with provider {} and model {}",
+        provider, model
+    );
+    let backend = get_provider(&app, &provider)?;
+    let request = ChatRequest::new(&provider, model, prompt, messages)
+        .with_options(options, &profiles)
+        .with_conversation(conversation_id);
+    streams
+        .run(backend.as_ref(), &request, &StreamSink::new(on_event))
+        .await
+}
+
+/// Stops an in-flight stream started by any of the streaming commands.
+#[tauri::command]
+pub async fn cancel_stream(
+    streams: tauri::State<'_, StreamRegistry>,
+    request_id: u64,
+) -> Result<bool, String> {
+    info!("Cancelling stream {}", request_id);
+    Ok(streams.cancel(request_id))
+}
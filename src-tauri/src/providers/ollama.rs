@@ -0,0 +1,612 @@
+// Ollama API
+//
+// Models that support tool calling get the client tools from `crate::tools`
+// when the request opts in. Tool calls arrive on the assistant message; we run
+// them, send the results back as `tool` messages and continue until the model
+// answers without calling any.
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use futures_util::stream::{self, StreamExt};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::ollama::models::model_capabilities;
+use crate::ollama::{KeepAlive, OllamaClient};
+use crate::tools::{self, ToolDefinition};
+
+use super::{
+    read_ndjson, stop_reason, ChatRequest, ChatResponse, ContentBlock, GenerationOptions,
+    LlmProvider, Message, MessageContent, ProviderCapabilities, StreamSink, Usage,
+};
+
+// Upper bound on tool round trips within one chat turn
+const MAX_TOOL_ROUNDS: usize = 5;
+
+// Model listing reads each model's capabilities from /api/show; a slow or
+// remote host mustn't hold up the model picker
+const SHOW_CONCURRENCY: usize = 4;
+const SHOW_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [OllamaMessage],
+    stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    options: serde_json::Map<String, serde_json::Value>,
+    // true, or an effort level for models that take one (gpt-oss)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<serde_json::Value>,
+    // Repeats a pinned keep-alive, which Ollama would otherwise reset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a KeepAlive>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    // Reasoning of thinking models, returned apart from the content
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    thinking: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    // The tool a `tool` message holds the result of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct OllamaChatResponse {
+    #[serde(default)]
+    message: OllamaMessage,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    // Set on the final chunk, with the token counts
+    done_reason: Option<String>,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+impl OllamaChatResponse {
+    fn usage(&self) -> Option<Usage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        Some(Usage {
+            input_tokens: self.prompt_eval_count.unwrap_or_default(),
+            output_tokens: self.eval_count.unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&Message> for OllamaMessage {
+    fn from(message: &Message) -> Self {
+        let images = match &message.content {
+            MessageContent::Text(_) => Vec::new(),
+            MessageContent::Multimodal(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Image { source } => Some(source.data.clone()),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        Self {
+            role: message.role.clone(),
+            content: message.content.text(),
+            images,
+            ..Default::default()
+        }
+    }
+}
+
+/// Tool schema in the function format Ollama takes.
+fn function_tool(definition: ToolDefinition) -> serde_json::Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": definition.name,
+            "description": definition.description,
+            "parameters": definition.input_schema
+        }
+    })
+}
+
+/// Maps generation options onto Ollama's model `options`; unset values keep
+/// the model's Modelfile defaults.
+fn model_options(options: &GenerationOptions) -> serde_json::Map<String, serde_json::Value> {
+    let mut map = serde_json::Map::new();
+    if let Some(max_tokens) = options.max_tokens {
+        map.insert("num_predict".to_string(), max_tokens.into());
+    }
+    if let Some(temperature) = options.temperature {
+        map.insert("temperature".to_string(), temperature.into());
+    }
+    if let Some(top_p) = options.top_p {
+        map.insert("top_p".to_string(), top_p.into());
+    }
+    if let Some(top_k) = options.top_k {
+        map.insert("top_k".to_string(), top_k.into());
+    }
+    if !options.stop.is_empty() {
+        map.insert("stop".to_string(), options.stop.clone().into());
+    }
+    if let Some(seed) = options.seed {
+        map.insert("seed".to_string(), seed.into());
+    }
+    if let Some(num_ctx) = options.num_ctx {
+        map.insert("num_ctx".to_string(), num_ctx.into());
+    }
+    if let Some(num_thread) = options.num_thread {
+        map.insert("num_thread".to_string(), num_thread.into());
+    }
+    if let Some(repeat_penalty) = options.repeat_penalty {
+        map.insert("repeat_penalty".to_string(), repeat_penalty.into());
+    }
+    map
+}
+
+/// Text accumulated over all rounds of a chat turn.
+#[derive(Default)]
+struct TurnOutput {
+    content: String,
+    reasoning: String,
+    // Summed over the rounds that reported token counts
+    usage: Option<Usage>,
+    // Of the last request
+    stop_reason: Option<String>,
+}
+
+impl TurnOutput {
+    fn into_response(self) -> ChatResponse {
+        ChatResponse {
+            content: self.content,
+            citations: None,
+            usage: self.usage,
+            stop_reason: self.stop_reason,
+            search: None,
+            reasoning: (!self.reasoning.is_empty()).then_some(self.reasoning),
+        }
+    }
+
+    fn push_text(&mut self, text: &str, sink: Option<&StreamSink>) {
+        self.content.push_str(text);
+        if let Some(sink) = sink {
+            sink.text(text);
+        }
+    }
+
+    /// Adds thinking, separated from that of earlier rounds.
+    fn push_reasoning(&mut self, text: &str, new_round: bool, sink: Option<&StreamSink>) {
+        let text = if new_round && !self.reasoning.is_empty() && !self.reasoning.ends_with('\n') {
+            format!("\n\n{}", text)
+        } else {
+            text.to_string()
+        };
+        self.reasoning.push_str(&text);
+        if let Some(sink) = sink {
+            sink.reasoning(&text);
+        }
+    }
+
+    /// Folds in one response or stream chunk, returning its tool calls.
+    fn push_chunk(
+        &mut self,
+        chunk: OllamaChatResponse,
+        new_round: bool,
+        sink: Option<&StreamSink>,
+    ) -> Vec<OllamaToolCall> {
+        if !chunk.message.thinking.is_empty() {
+            self.push_reasoning(&chunk.message.thinking, new_round, sink);
+        }
+        if !chunk.message.content.is_empty() {
+            self.push_text(&chunk.message.content, sink);
+        }
+        if chunk.done {
+            if let Some(usage) = chunk.usage() {
+                self.usage.get_or_insert_with(Usage::default).add(&usage);
+            }
+            self.stop_reason = chunk.done_reason.as_deref().map(stop_reason);
+        }
+        chunk.message.tool_calls
+    }
+}
+
+pub struct OllamaProvider {
+    app: tauri::AppHandle,
+}
+
+impl OllamaProvider {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self { app }
+    }
+
+    async fn send(
+        &self,
+        client: &OllamaClient,
+        request: &ChatRequest,
+        messages: &[OllamaMessage],
+        stream: bool,
+    ) -> Result<reqwest::Response, String> {
+        let think = request
+            .options
+            .reasoning
+            .as_ref()
+            .map(|reasoning| match &reasoning.effort {
+                Some(effort) => json!(effort),
+                None => json!(true),
+            });
+        let tools = if request.options.client_tools {
+            tools::definitions()
+                .into_iter()
+                .map(function_tool)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let body = OllamaChatRequest {
+            model: &request.model,
+            messages,
+            stream,
+            tools,
+            options: model_options(&request.options),
+            think,
+            keep_alive: client.keep_alive(&request.model),
+        };
+
+        info!(
+            "Sending chat request to Ollama at {} with model: {}",
+            client.base_url(),
+            request.model
+        );
+        let response = client
+            .post("/api/chat")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| client.connect_error(e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!("Ollama API error: {}", error_text);
+            return Err(format!("Ollama API error: {}", error_text));
+        }
+
+        Ok(response)
+    }
+
+    /// Reads a whole response into `output`, returning its tool calls.
+    async fn read_response(
+        response: reqwest::Response,
+        output: &mut TurnOutput,
+    ) -> Result<Vec<OllamaToolCall>, String> {
+        let parsed = response
+            .json::<OllamaChatResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+
+        if let Some(e) = parsed.error {
+            return Err(format!("Ollama API error: {}", e));
+        }
+        Ok(output.push_chunk(parsed, true, None))
+    }
+
+    /// Streams a response into `output` and the sink, returning its tool
+    /// calls.
+    async fn read_stream(
+        response: reqwest::Response,
+        output: &mut TurnOutput,
+        sink: &StreamSink,
+    ) -> Result<Vec<OllamaToolCall>, String> {
+        let mut tool_calls = Vec::new();
+        let mut stream_error = None;
+        let mut new_round = true;
+
+        read_ndjson(response, "Ollama", |line| {
+            match serde_json::from_str::<OllamaChatResponse>(line) {
+                Ok(chunk) => {
+                    if let Some(e) = chunk.error {
+                        error!("Ollama stream error: {}", e);
+                        stream_error = Some(e);
+                        return;
+                    }
+                    let done = chunk.done;
+                    let thinking = !chunk.message.thinking.is_empty();
+                    tool_calls.extend(output.push_chunk(chunk, new_round, Some(sink)));
+                    new_round &= !thinking;
+                    if done {
+                        info!("Ollama stream finished");
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to parse Ollama chunk: {} - Error: {}", line, e);
+                }
+            }
+        })
+        .await?;
+
+        if let Some(e) = stream_error {
+            return Err(format!("Ollama API error: {}", e));
+        }
+        Ok(tool_calls)
+    }
+
+    /// Runs a chat turn, executing tool calls until the model answers.
+    async fn run(
+        &self,
+        request: &ChatRequest,
+        sink: Option<&StreamSink>,
+    ) -> Result<ChatResponse, String> {
+        let client = OllamaClient::load();
+        let mut messages: Vec<OllamaMessage> = request
+            .messages_with_system()
+            .iter()
+            .map(OllamaMessage::from)
+            .collect();
+        let mut output = TurnOutput::default();
+        let mut tool_rounds = 0;
+
+        loop {
+            let round_start = output.content.len();
+            let response = self
+                .send(&client, request, &messages, sink.is_some())
+                .await?;
+            let tool_calls = match sink {
+                Some(sink) => Self::read_stream(response, &mut output, sink).await?,
+                None => Self::read_response(response, &mut output).await?,
+            };
+
+            if tool_calls.is_empty() {
+                break;
+            }
+            if tool_rounds == MAX_TOOL_ROUNDS {
+                error!("Ollama still calling tools after {} rounds", tool_rounds);
+                break;
+            }
+            tool_rounds += 1;
+
+            messages.push(OllamaMessage {
+                role: "assistant".to_string(),
+                content: output.content[round_start..].to_string(),
+                tool_calls: tool_calls.clone(),
+                ..Default::default()
+            });
+            for (index, call) in tool_calls.iter().enumerate() {
+                // Ollama doesn't number tool calls, but the UI pairs them up
+                let id = format!("ollama-tool-{}-{}", tool_rounds, index);
+                let name = &call.function.name;
+                if let Some(sink) = sink {
+                    sink.tool_use(&id, name, &call.function.arguments);
+                }
+                let result = tools::execute(&self.app, name, &call.function.arguments).await;
+                if let Some(sink) = sink {
+                    sink.tool_result(&id, name, &result);
+                }
+                messages.push(OllamaMessage {
+                    role: "tool".to_string(),
+                    content: result.content,
+                    tool_name: Some(name.clone()),
+                    ..Default::default()
+                });
+            }
+
+            // Keep the text before and after the tool call apart
+            if !output.content.is_empty() && !output.content.ends_with('\n') {
+                output.push_text("\n\n", sink);
+            }
+        }
+
+        info!(
+            "Completed Ollama chat ({} chars, {} tool rounds)",
+            output.content.len(),
+            tool_rounds
+        );
+        Ok(output.into_response())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn id(&self) -> &str {
+        "ollama"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            vision: true,
+            web_search: false,
+            citations: false,
+        }
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        self.run(request, None).await
+    }
+
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String> {
+        self.run(request, Some(sink)).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        let client = OllamaClient::load();
+        let response = client
+            .get("/api/tags")
+            .timeout(SHOW_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| client.connect_error(e))?;
+
+        let data = response.json::<serde_json::Value>().await.map_err(|e| {
+            error!("Failed to parse Ollama response: {}", e);
+            format!("Failed to parse Ollama response: {}", e)
+        })?;
+
+        let Some(models) = data.get("models").and_then(|m| m.as_array()) else {
+            info!("No models found in Ollama response");
+            return Ok(Vec::new());
+        };
+
+        // Capabilities come from /api/show, cached per digest
+        let names: Vec<(usize, String, String)> = models
+            .iter()
+            .enumerate()
+            .filter_map(|(index, model)| {
+                let name = model.get("name").and_then(|n| n.as_str())?;
+                let digest = model.get("digest").and_then(|d| d.as_str()).unwrap_or(name);
+                Some((index, name.to_string(), digest.to_string()))
+            })
+            .collect();
+        let mut capabilities = vec![None; models.len()];
+        let mut lookups = stream::iter(names)
+            .map(|(index, name, digest)| {
+                let client = &client;
+                async move {
+                    let lookup = model_capabilities(client, &name, &digest);
+                    match tokio::time::timeout(SHOW_TIMEOUT, lookup).await {
+                        Ok(Ok(capabilities)) => Some((index, capabilities)),
+                        Ok(Err(e)) => {
+                            error!("Failed to read capabilities of {}: {}", name, e);
+                            None
+                        }
+                        Err(_) => {
+                            error!("Timed out reading capabilities of {}", name);
+                            None
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(SHOW_CONCURRENCY);
+        while let Some(lookup) = lookups.next().await {
+            if let Some((index, found)) = lookup {
+                capabilities[index] = Some(found);
+            }
+        }
+
+        let ollama_models = models
+            .iter()
+            .zip(capabilities)
+            .filter_map(|(model, capabilities)| {
+                let name = model.get("name").and_then(|n| n.as_str())?;
+                let modified_at = model
+                    .get("modified_at")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("Unknown");
+                let details = model
+                    .get("details")
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({}));
+                let parameter_size = details
+                    .get("parameter_size")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or("Unknown");
+
+                // Format the date to human-readable format
+                let formatted_date = match DateTime::parse_from_rfc3339(modified_at) {
+                    Ok(dt) => dt
+                        .with_timezone(&chrono::Local)
+                        .format("%b %d, %Y - %I:%M %p %Z")
+                        .to_string(),
+                    Err(_) => modified_at.to_string(),
+                };
+
+                // Capabilities are null when /api/show failed, so callers
+                // can fall back
+                let capabilities = capabilities.unwrap_or_default();
+                let family = capabilities.family.or_else(|| {
+                    details
+                        .get("family")
+                        .and_then(|f| f.as_str())
+                        .map(|f| f.to_string())
+                });
+                Some(serde_json::json!({
+                    "id": name,
+                    "name": name,
+                    "description": format!("{} - {}", parameter_size, formatted_date),
+                    "provider": "ollama",
+                    "capabilities": capabilities.capabilities,
+                    "details": {
+                        "modified_at": modified_at,
+                        "parameter_size": parameter_size,
+                        "quantization_level": details.get("quantization_level").and_then(|q| q.as_str()).unwrap_or("Unknown"),
+                        "family": family,
+                        "context_length": capabilities.context_length,
+                        "template": capabilities.template
+                    }
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        info!("Successfully fetched {} Ollama models", ollama_models.len());
+        Ok(ollama_models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_tool_calls_and_usage_from_response() {
+        let chunk: OllamaChatResponse = serde_json::from_value(json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    { "function": { "name": "getWeather", "arguments": { "location": "Boston, MA" } } }
+                ]
+            },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 120,
+            "eval_count": 18
+        }))
+        .unwrap();
+
+        let mut output = TurnOutput::default();
+        let tool_calls = output.push_chunk(chunk, true, None);
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].function.name, "getWeather");
+        assert_eq!(tool_calls[0].function.arguments["location"], "Boston, MA");
+        assert_eq!(output.usage.as_ref().map(|u| u.output_tokens), Some(18));
+
+        let result = OllamaMessage {
+            role: "tool".to_string(),
+            content: "{}".to_string(),
+            tool_name: Some("getWeather".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({ "role": "tool", "content": "{}", "tool_name": "getWeather" })
+        );
+    }
+}
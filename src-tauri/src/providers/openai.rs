@@ -0,0 +1,455 @@
+// OpenAI API (chat completions)
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use super::think::{split_think, stream_segments, ThinkSplitter};
+use super::{
+    check_response, map_send_error, read_sse, stop_reason, ChatRequest, ChatResponse,
+    CompletionUsage, ContentBlock, LlmProvider, Message, MessageContent, ProviderCapabilities,
+    StreamSink, Usage, MODEL_LIST_CLIENT,
+};
+use crate::endpoints::OpenAiEndpoint;
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    messages: Vec<OpenAiMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    // OpenAI's reasoning models only accept this in place of max_tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    // Not part of the OpenAI API, but understood by vLLM, llama.cpp and others
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct OpenAiMessage {
+    role: String,
+    content: OpenAiContent,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum OpenAiContent {
+    Text(String),
+    Parts(Vec<OpenAiPart>),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum OpenAiPart {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: OpenAiImageUrl },
+    #[serde(rename = "file")]
+    File { file: OpenAiFile },
+}
+
+#[derive(Serialize)]
+struct OpenAiImageUrl {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct OpenAiFile {
+    filename: String,
+    file_data: String,
+}
+
+impl From<&Message> for OpenAiMessage {
+    fn from(message: &Message) -> Self {
+        let content = match &message.content {
+            MessageContent::Text(text) => OpenAiContent::Text(text.clone()),
+            MessageContent::Multimodal(blocks) => OpenAiContent::Parts(
+                blocks
+                    .iter()
+                    .filter_map(|block| match block {
+                        // Vision input is passed as a base64 data URL
+                        ContentBlock::Image { source } => Some(OpenAiPart::ImageUrl {
+                            image_url: OpenAiImageUrl {
+                                url: format!("data:{};base64,{}", source.media_type, source.data),
+                            },
+                        }),
+                        ContentBlock::Document { source, title, .. } if source.is_pdf() => {
+                            Some(OpenAiPart::File {
+                                file: OpenAiFile {
+                                    filename: title
+                                        .clone()
+                                        .unwrap_or_else(|| "document.pdf".to_string()),
+                                    file_data: format!(
+                                        "data:{};base64,{}",
+                                        source.media_type, source.data
+                                    ),
+                                },
+                            })
+                        }
+                        _ => block.as_text().map(|text| OpenAiPart::Text { text }),
+                    })
+                    .collect(),
+            ),
+        };
+
+        Self {
+            role: message.role.clone(),
+            content,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    choices: Vec<OpenAiChoice>,
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiChoice {
+    message: OpenAiResponseMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponseMessage {
+    content: Option<String>,
+    #[serde(flatten)]
+    reasoning: ReasoningFields,
+}
+
+// Streaming response structures
+#[derive(Deserialize, Debug)]
+struct OpenAiStreamResponse {
+    #[serde(default)]
+    choices: Vec<OpenAiStreamChoice>,
+    // Only set on the final chunk
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAiStreamChoice {
+    delta: OpenAiStreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAiStreamDelta {
+    content: Option<String>,
+    #[serde(flatten)]
+    reasoning: ReasoningFields,
+}
+
+// OpenAI keeps reasoning to itself; compatible servers return it under one
+// of these names (vLLM and DeepSeek the first, OpenRouter and Ollama the
+// second), or inline it as <think> tags
+#[derive(Deserialize, Debug, Default)]
+struct ReasoningFields {
+    reasoning_content: Option<String>,
+    reasoning: Option<String>,
+}
+
+impl ReasoningFields {
+    fn text(&self) -> Option<&str> {
+        self.reasoning_content
+            .as_deref()
+            .or(self.reasoning.as_deref())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Chat completions client for OpenAI and servers that speak the same API.
+pub struct OpenAiProvider {
+    id: String,
+    label: String,
+    base_url: String,
+    // Key storage slot holding the API key
+    key_provider: String,
+    // Local servers usually run without a key
+    key_required: bool,
+}
+
+impl OpenAiProvider {
+    pub fn openai() -> Self {
+        Self {
+            id: "openai".to_string(),
+            label: "OpenAI".to_string(),
+            base_url: OPENAI_BASE_URL.to_string(),
+            key_provider: "openai".to_string(),
+            key_required: true,
+        }
+    }
+
+    /// A user-registered server speaking the same API.
+    pub fn compatible(endpoint: &OpenAiEndpoint) -> Self {
+        Self {
+            id: endpoint.provider_id(),
+            label: endpoint.name.clone(),
+            base_url: endpoint.base_url.clone(),
+            key_provider: endpoint.key_provider(),
+            key_required: false,
+        }
+    }
+
+    /// OpenAI's key may also come from OPENAI_API_KEY; endpoint keys are
+    /// optional and only ever set in Settings.
+    fn api_key(&self) -> Result<Option<String>, String> {
+        if self.key_required {
+            return crate::load_api_key(&self.key_provider).map(Some);
+        }
+        match crate::get_api_key_file(&self.key_provider)? {
+            Some(key) if !key.is_empty() => Ok(Some(key)),
+            _ => Ok(None),
+        }
+    }
+
+    fn authorized(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        Ok(match self.api_key()? {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        })
+    }
+
+    async fn send(&self, request: &ChatRequest, stream: bool) -> Result<reqwest::Response, String> {
+        let options = &request.options;
+        let is_openai = self.id == "openai";
+        let body = OpenAiRequest {
+            model: &request.model,
+            messages: request
+                .messages_with_system()
+                .iter()
+                .map(OpenAiMessage::from)
+                .collect(),
+            max_tokens: options.max_tokens.filter(|_| !is_openai),
+            max_completion_tokens: options.max_tokens.filter(|_| is_openai),
+            temperature: options.temperature,
+            top_p: options.top_p,
+            top_k: options.top_k.filter(|_| !is_openai),
+            stop: &options.stop,
+            seed: options.seed,
+            reasoning_effort: options
+                .reasoning
+                .as_ref()
+                .and_then(|reasoning| reasoning.effort.as_deref()),
+            stream: stream.then_some(true),
+            // Compatible servers vary here; many report usage unasked anyway
+            stream_options: (stream && is_openai)
+                .then(|| serde_json::json!({ "include_usage": true })),
+        };
+
+        info!(
+            "Sending request to {} API with model: {}",
+            self.label, request.model
+        );
+        let response = self
+            .authorized(reqwest::Client::new().post(format!("{}/chat/completions", self.base_url)))?
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| map_send_error(e, &self.label))?;
+
+        check_response(response, &self.label).await
+    }
+
+    /// OpenAI's model list includes embeddings, audio and image models.
+    fn is_chat_model(&self, id: &str) -> bool {
+        if self.id != "openai" {
+            return true;
+        }
+        let chat_prefix = ["gpt-", "chatgpt-", "o1", "o3", "o4"]
+            .iter()
+            .any(|prefix| id.starts_with(prefix));
+        let excluded = ["audio", "realtime", "transcribe", "tts", "image", "search"]
+            .iter()
+            .any(|kind| id.contains(kind));
+        chat_prefix && !excluded
+    }
+
+    /// Chat models from `/models`, with errors passed through.
+    pub async fn fetch_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        let response = self
+            .authorized(MODEL_LIST_CLIENT.get(format!("{}/models", self.base_url)))?
+            .send()
+            .await
+            .map_err(|e| map_send_error(e, &self.label))?;
+        let response = check_response(response, &self.label).await?;
+
+        let data = response.json::<serde_json::Value>().await.map_err(|e| {
+            error!("Failed to parse {} models response: {}", self.label, e);
+            format!("Failed to parse {} models response: {}", self.label, e)
+        })?;
+
+        let mut models: Vec<serde_json::Value> = data
+            .get("data")
+            .and_then(|d| d.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model.get("id").and_then(|v| v.as_str()))
+                    .filter(|id| self.is_chat_model(id))
+                    .map(|id| {
+                        serde_json::json!({
+                            "id": id,
+                            "name": id,
+                            "description": format!("{} API model", self.label),
+                            "provider": self.id
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        models.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+        info!(
+            "Successfully fetched {} {} models",
+            models.len(),
+            self.label
+        );
+        Ok(models)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            vision: true,
+            web_search: false,
+            citations: false,
+        }
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        let response = self.send(request, false).await?;
+
+        let parsed = response.json::<OpenAiResponse>().await.map_err(|e| {
+            error!("Failed to parse {} API response: {}", self.label, e);
+            format!("Failed to parse response: {}", e)
+        })?;
+
+        let Some(choice) = parsed.choices.into_iter().next() else {
+            error!("{} response choices array is empty", self.label);
+            return Err(format!("Empty response from {} API", self.label));
+        };
+
+        let (content, inline_reasoning) =
+            split_think(choice.message.content.as_deref().unwrap_or_default());
+        Ok(ChatResponse {
+            content,
+            citations: None,
+            usage: parsed.usage.map(Usage::from),
+            stop_reason: choice.finish_reason.as_deref().map(stop_reason),
+            search: None,
+            reasoning: choice
+                .message
+                .reasoning
+                .text()
+                .map(|text| text.to_string())
+                .or(inline_reasoning),
+        })
+    }
+
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String> {
+        let response = self.send(request, true).await?;
+        let mut full_response = String::new();
+        let mut reasoning = String::new();
+        let mut splitter = ThinkSplitter::new();
+        let mut usage = None;
+        let mut finish_reason = None;
+
+        read_sse(response, &self.label, |event| {
+            match serde_json::from_str::<OpenAiStreamResponse>(&event.data) {
+                Ok(parsed) => {
+                    if let Some(chunk_usage) = parsed.usage {
+                        usage = Some(Usage::from(chunk_usage));
+                    }
+                    if let Some(reason) = parsed
+                        .choices
+                        .first()
+                        .and_then(|c| c.finish_reason.as_deref())
+                    {
+                        finish_reason = Some(stop_reason(reason));
+                    }
+                    let Some(delta) = parsed.choices.first().map(|c| &c.delta) else {
+                        return;
+                    };
+                    if let Some(text) = delta.reasoning.text() {
+                        reasoning.push_str(text);
+                        sink.reasoning(text);
+                    }
+                    if let Some(content) = &delta.content {
+                        stream_segments(
+                            splitter.push(content),
+                            sink,
+                            &mut full_response,
+                            &mut reasoning,
+                        );
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to parse JSON from {} chunk: {} - Error: {}",
+                        self.label, event.data, e
+                    );
+                }
+            }
+        })
+        .await?;
+        stream_segments(splitter.finish(), sink, &mut full_response, &mut reasoning);
+
+        info!(
+            "Streaming completed from {} ({} chars)",
+            self.label,
+            full_response.len()
+        );
+
+        Ok(ChatResponse {
+            content: full_response,
+            citations: None,
+            usage,
+            stop_reason: finish_reason,
+            search: None,
+            reasoning: (!reasoning.is_empty()).then_some(reasoning),
+        })
+    }
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        match self.fetch_models().await {
+            Ok(models) => Ok(models),
+            Err(e) => {
+                info!("Skipping {} model list: {}", self.label, e);
+                Ok(vec![])
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+// Perplexity API
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde::Deserialize;
+
+use super::think::{split_think, stream_segments, ThinkSplitter};
+use super::{
+    check_response, map_send_error, read_sse, stop_reason, ChatRequest, ChatResponse, Citation,
+    CompletionUsage, LlmProvider, ProviderCapabilities, SearchDetails, SearchImage, SearchResult,
+    StreamSink, Usage,
+};
+
+const CHAT_URL: &str = "https://api.perplexity.ai/chat/completions";
+
+#[derive(Deserialize)]
+struct PerplexityResponse {
+    choices: Vec<PerplexityChoice>,
+    citations: Option<Vec<String>>,
+    search_results: Option<Vec<SearchResult>>,
+    images: Option<Vec<SearchImage>>,
+    related_questions: Option<Vec<String>>,
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Deserialize)]
+struct PerplexityChoice {
+    message: PerplexityMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PerplexityMessage {
+    content: String,
+}
+
+// Streaming response structures
+#[derive(Deserialize, Debug)]
+struct PerplexityStreamResponse {
+    choices: Vec<PerplexityStreamChoice>,
+    citations: Option<Vec<String>>,
+    search_results: Option<Vec<SearchResult>>,
+    images: Option<Vec<SearchImage>>,
+    related_questions: Option<Vec<String>>,
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PerplexityStreamChoice {
+    delta: PerplexityStreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PerplexityStreamDelta {
+    content: Option<String>,
+}
+
+/// The conversation as Perplexity accepts it: after the system prompt, turns
+/// must alternate user/assistant, starting and ending with the user.
+/// Consecutive turns of one role are merged and empty ones dropped.
+fn alternating_turns(request: &ChatRequest) -> Result<Vec<(&'static str, String)>, String> {
+    let mut turns: Vec<(&'static str, String)> = Vec::new();
+
+    for message in request.turns() {
+        let role = if message.role == "assistant" {
+            "assistant"
+        } else {
+            "user"
+        };
+        let text = message.content.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        match turns.last_mut() {
+            Some((last_role, content)) if *last_role == role => {
+                content.push_str("\n\n");
+                content.push_str(&text);
+            }
+            None if role == "assistant" => {}
+            _ => turns.push((role, text)),
+        }
+    }
+
+    // The model answers the last user turn
+    while turns.last().is_some_and(|(role, _)| *role == "assistant") {
+        turns.pop();
+    }
+    if turns.is_empty() {
+        return Err("Perplexity needs a user message to answer".to_string());
+    }
+    Ok(turns)
+}
+
+/// Citations for the answer, titled from the search results when Perplexity
+/// returned them, plus the search details for the done payload.
+fn search_output(
+    citations: Option<Vec<String>>,
+    search: SearchDetails,
+) -> (Option<Vec<Citation>>, Option<SearchDetails>) {
+    let citations = citations.map(|urls| {
+        urls.into_iter()
+            .map(|url| match search.results.iter().find(|r| r.url == url) {
+                Some(result) => Citation {
+                    url,
+                    title: result.title.clone(),
+                    cited_text: result.snippet.clone(),
+                    ..Default::default()
+                },
+                None => Citation::from_url(url),
+            })
+            .collect()
+    });
+    (citations, (!search.is_empty()).then_some(search))
+}
+
+pub struct PerplexityProvider {
+    app: tauri::AppHandle,
+}
+
+impl PerplexityProvider {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self { app }
+    }
+
+    fn build_request(
+        &self,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<serde_json::Value, String> {
+        let turns = alternating_turns(request)?;
+        let mut messages = Vec::new();
+        if let Some(system) = request.system_prompt() {
+            messages.push(serde_json::json!({ "role": "system", "content": system }));
+        }
+        messages.extend(
+            turns
+                .into_iter()
+                .map(|(role, content)| serde_json::json!({ "role": role, "content": content })),
+        );
+
+        let options = &request.options;
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "messages": messages,
+            "max_tokens": options.max_tokens.unwrap_or(1024),
+            "temperature": options.temperature.unwrap_or(0.7)
+        });
+        // Perplexity takes no stop sequences or seed
+        if let Some(top_p) = options.top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(top_k) = options.top_k {
+            body["top_k"] = serde_json::json!(top_k);
+        }
+        if let Some(search) = &options.perplexity_search {
+            if let Some(recency) = &search.search_recency_filter {
+                body["search_recency_filter"] = serde_json::json!(recency);
+            }
+            if !search.search_domain_filter.is_empty() {
+                body["search_domain_filter"] = serde_json::json!(search.search_domain_filter);
+            }
+            if let Some(mode) = &search.search_mode {
+                body["search_mode"] = serde_json::json!(mode);
+            }
+            if let Some(return_images) = search.return_images {
+                body["return_images"] = serde_json::json!(return_images);
+            }
+            if let Some(related) = search.return_related_questions {
+                body["return_related_questions"] = serde_json::json!(related);
+            }
+            if let Some(size) = &search.search_context_size {
+                body["web_search_options"] = serde_json::json!({ "search_context_size": size });
+            }
+        }
+        if stream {
+            body["stream"] = serde_json::json!(true);
+        }
+        Ok(body)
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response, String> {
+        // Load API key from secure storage
+        let api_key = match crate::get_api_key(self.app.clone(), "perplexity".to_string()).await? {
+            Some(key) => key,
+            None => {
+                return Err("Perplexity API key not found. Please add it in Settings.".to_string())
+            }
+        };
+
+        info!("Sending request to Perplexity API...");
+        let response = reqwest::Client::new()
+            .post(CHAT_URL)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| map_send_error(e, "Perplexity"))?;
+
+        check_response(response, "Perplexity").await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for PerplexityProvider {
+    fn id(&self) -> &str {
+        "perplexity"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            vision: false,
+            web_search: true,
+            citations: true,
+        }
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, String> {
+        let response = self.send(&self.build_request(request, false)?).await?;
+
+        let perplexity_response = response.json::<PerplexityResponse>().await.map_err(|e| {
+            error!("Failed to parse Perplexity API response: {}", e);
+            format!("Failed to parse response: {}", e)
+        })?;
+
+        let Some(choice) = perplexity_response.choices.into_iter().next() else {
+            error!("Perplexity response choices array is empty");
+            return Err("Empty response from Perplexity API".to_string());
+        };
+
+        let (citations, search) = search_output(
+            perplexity_response.citations,
+            SearchDetails {
+                results: perplexity_response.search_results.unwrap_or_default(),
+                images: perplexity_response.images.unwrap_or_default(),
+                related_questions: perplexity_response.related_questions.unwrap_or_default(),
+            },
+        );
+
+        // sonar-reasoning models inline their reasoning as <think> tags
+        let (content, reasoning) = split_think(&choice.message.content);
+        Ok(ChatResponse {
+            content,
+            citations,
+            usage: perplexity_response.usage.map(Usage::from),
+            stop_reason: choice.finish_reason.as_deref().map(stop_reason),
+            search,
+            reasoning,
+        })
+    }
+
+    async fn stream_chat(
+        &self,
+        request: &ChatRequest,
+        sink: &StreamSink,
+    ) -> Result<ChatResponse, String> {
+        let response = self.send(&self.build_request(request, true)?).await?;
+        let mut full_response = String::new();
+        let mut reasoning = String::new();
+        let mut splitter = ThinkSplitter::new();
+        let mut citations: Option<Vec<String>> = None;
+        let mut search = SearchDetails::default();
+        let mut usage = None;
+        let mut finish_reason = None;
+
+        read_sse(response, "Perplexity", |event| {
+            match serde_json::from_str::<PerplexityStreamResponse>(&event.data) {
+                Ok(parsed) => {
+                    // Capture citations if present (they come in the final chunk)
+                    if let Some(cites) = parsed.citations {
+                        info!("Received {} citations", cites.len());
+                        citations = Some(cites);
+                    }
+                    // Like citations, these are repeated in later chunks
+                    if let Some(results) = parsed.search_results {
+                        search.results = results;
+                    }
+                    if let Some(images) = parsed.images {
+                        search.images = images;
+                    }
+                    if let Some(questions) = parsed.related_questions {
+                        search.related_questions = questions;
+                    }
+                    // Running totals; the last chunk has the final counts
+                    if let Some(chunk_usage) = parsed.usage {
+                        usage = Some(Usage::from(chunk_usage));
+                    }
+                    if let Some(reason) = parsed
+                        .choices
+                        .first()
+                        .and_then(|c| c.finish_reason.as_deref())
+                    {
+                        finish_reason = Some(stop_reason(reason));
+                    }
+
+                    // Extract content from the first choice's delta if available
+                    if let Some(content) = parsed
+                        .choices
+                        .first()
+                        .and_then(|c| c.delta.content.as_ref())
+                    {
+                        stream_segments(
+                            splitter.push(content),
+                            sink,
+                            &mut full_response,
+                            &mut reasoning,
+                        );
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to parse JSON from Perplexity event: {} - Error: {}",
+                        event.data, e
+                    );
+                }
+            }
+        })
+        .await?;
+        stream_segments(splitter.finish(), sink, &mut full_response, &mut reasoning);
+
+        info!(
+            "Streaming completed from Perplexity ({} chars)",
+            full_response.len()
+        );
+
+        let (citations, search) = search_output(citations, search);
+
+        Ok(ChatResponse {
+            content: full_response,
+            citations,
+            usage,
+            stop_reason: finish_reason,
+            search,
+            reasoning: (!reasoning.is_empty()).then_some(reasoning),
+        })
+    }
+
+    async fn list_models(&self) -> Result<Vec<serde_json::Value>, String> {
+        let models = [
+            (
+                "sonar-deep-research",
+                "Sonar Deep Research",
+                "Deep research with comprehensive analysis",
+            ),
+            (
+                "sonar-reasoning-pro",
+                "Sonar Reasoning Pro",
+                "Advanced reasoning capabilities",
+            ),
+            ("sonar-reasoning", "Sonar Reasoning", "Core reasoning model"),
+            (
+                "sonar-pro",
+                "Sonar Pro",
+                "Professional grade search and chat",
+            ),
+            ("sonar", "Sonar", "Standard search and chat model"),
+        ];
+
+        Ok(models
+            .iter()
+            .map(|(id, name, description)| {
+                serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "description": description,
+                    "provider": "perplexity"
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Message, MessageContent};
+
+    fn message(role: &str, text: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: MessageContent::Text(text.to_string()),
+        }
+    }
+
+    fn turns(messages: Vec<Message>) -> Result<Vec<(&'static str, String)>, String> {
+        alternating_turns(&ChatRequest::new(
+            "perplexity",
+            "sonar".to_string(),
+            String::new(),
+            messages,
+        ))
+    }
+
+    #[test]
+    fn merges_consecutive_turns_of_one_role() {
+        let result = turns(vec![
+            message("user", "First"),
+            message("user", "Second"),
+            message("assistant", "Answer"),
+            message("assistant", ""),
+            message("user", "Third"),
+        ]);
+
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                ("user", "First\n\nSecond".to_string()),
+                ("assistant", "Answer".to_string()),
+                ("user", "Third".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_leading_assistant_turns() {
+        let result = turns(vec![
+            message("assistant", "Hi, how can I help?"),
+            message("user", "Question"),
+        ]);
+
+        assert_eq!(result.unwrap(), vec![("user", "Question".to_string())]);
+    }
+
+    #[test]
+    fn rejects_history_without_user_turn() {
+        assert!(turns(Vec::new()).is_err());
+        assert!(turns(vec![message("assistant", "Hello")]).is_err());
+    }
+}
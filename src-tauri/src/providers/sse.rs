@@ -0,0 +1,261 @@
+// Incremental Server-Sent Events decoder
+//
+// Follows the WHATWG event stream format: input is buffered as raw bytes and
+// only complete lines are decoded, so a multi-byte character split across
+// network chunks is never mangled. Lines may end in CRLF, LF or CR.
+
+/// One dispatched event.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SseEvent {
+    /// The `event:` field, or "message" when the server didn't name it.
+    pub event: String,
+    /// All `data:` lines of the event, joined with `\n`.
+    pub data: String,
+    /// Last event id seen on the stream, as the spec carries it forward.
+    pub id: Option<String>,
+    /// Reconnection time requested with `retry:`, in milliseconds.
+    pub retry: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    // Bytes at the start of `buffer` already scanned without a line break,
+    // so a long line arriving in small chunks is only scanned once
+    scanned: usize,
+    // A chunk ended in CR; a LF starting the next chunk belongs to that line
+    after_cr: bool,
+    bom_checked: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consumes a network chunk and returns the events it completed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        // An empty chunk says nothing about the LF a pending CR may pair with
+        if chunk.is_empty() {
+            return Vec::new();
+        }
+        let mut chunk = chunk;
+        if self.after_cr && chunk.first() == Some(&b'\n') {
+            chunk = &chunk[1..];
+        }
+        self.after_cr = false;
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        let mut i = self.scanned;
+        while i < self.buffer.len() {
+            match self.buffer[i] {
+                b'\n' => {
+                    self.process_line(start, i, &mut events);
+                    start = i + 1;
+                }
+                b'\r' => {
+                    self.process_line(start, i, &mut events);
+                    if i + 1 == self.buffer.len() {
+                        self.after_cr = true;
+                    } else if self.buffer[i + 1] == b'\n' {
+                        i += 1;
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.buffer.drain(..start);
+        self.scanned = self.buffer.len();
+
+        events
+    }
+
+    fn process_line(&mut self, start: usize, end: usize, events: &mut Vec<SseEvent>) {
+        let mut bytes = &self.buffer[start..end];
+        if !self.bom_checked {
+            self.bom_checked = true;
+            bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        }
+        let line = String::from_utf8_lossy(bytes).into_owned();
+
+        if line.is_empty() {
+            if let Some(event) = self.dispatch() {
+                events.push(event);
+            }
+            return;
+        }
+        if line.starts_with(':') {
+            // Comment, used by servers as a keep-alive
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(retry) = value.parse::<u64>() {
+                    if value.bytes().all(|b| b.is_ascii_digit()) {
+                        self.retry = Some(retry);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends the current event at a blank line. Events without data are
+    /// dropped, as the spec requires.
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+
+        Some(SseEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+            retry: self.retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        chunks
+            .iter()
+            .flat_map(|chunk| decoder.feed(chunk))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_codepoint_split_across_chunks() {
+        // "é" is 0xC3 0xA9 and "🙂" is four bytes; cut both in half
+        let input = "data: {\"text\":\"café 🙂\"}\n\n".as_bytes();
+        let e_acute = input.iter().position(|&b| b == 0xC3).unwrap();
+        let emoji = input.iter().position(|&b| b == 0xF0).unwrap();
+
+        let events = feed_all(&[
+            &input[..e_acute + 1],
+            &input[e_acute + 1..emoji + 2],
+            &input[emoji + 2..],
+        ]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "{\"text\":\"café 🙂\"}");
+    }
+
+    #[test]
+    fn decodes_event_split_across_chunks() {
+        let events = feed_all(&[
+            b"event: content_block_delta\nda",
+            b"ta: {\"a\":",
+            b"1}\n",
+            b"\nevent: ping\ndata: {}\n\n",
+        ]);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "content_block_delta");
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert_eq!(events[1].event, "ping");
+        assert_eq!(events[1].data, "{}");
+    }
+
+    #[test]
+    fn handles_crlf_and_cr_line_endings() {
+        // The CRLF of the blank line is split between chunks
+        let events = feed_all(&[b"data: one\r\n\r", b"\ndata: two\r\r"]);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data, "one");
+        assert_eq!(events[1].data, "two");
+    }
+
+    #[test]
+    fn keeps_pending_cr_across_empty_chunks() {
+        // CR, nothing, then LF is a single line break
+        let events = feed_all(&[b"data: a\r", b"", b"\ndata: b\n\n"]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "a\nb");
+    }
+
+    #[test]
+    fn decodes_long_line_fed_byte_by_byte() {
+        let data = "x".repeat(5000);
+        let input = format!("data: {}\n\n", data);
+        let chunks: Vec<&[u8]> = input.as_bytes().chunks(1).collect();
+
+        let events = feed_all(&chunks);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, data);
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let events = feed_all(&[b"data: first\ndata:second\ndata\n\n"]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "first\nsecond\n");
+        assert_eq!(events[0].event, "message");
+    }
+
+    #[test]
+    fn tracks_id_and_retry_and_skips_comments() {
+        let events =
+            feed_all(&[b": keep-alive\n\nid: 7\nretry: 3000\ndata: a\n\nretry: soon\ndata: b\n\n"]);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[0].retry, Some(3000));
+        // The id carries over to later events; a bad retry is ignored
+        assert_eq!(events[1].id.as_deref(), Some("7"));
+        assert_eq!(events[1].retry, Some(3000));
+    }
+
+    #[test]
+    fn drops_events_without_data_and_unterminated_events() {
+        let events = feed_all(&[b"event: ping\n\ndata: pending"]);
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn strips_leading_bom() {
+        let events = feed_all(&[b"\xEF\xBB\xBFdata: x\n\n"]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "x");
+    }
+}
@@ -0,0 +1,156 @@
+// Splitter for `<think>` sections inlined into an answer
+//
+// Perplexity's sonar-reasoning models, and reasoning models served through
+// chat completions APIs that don't separate it (DeepSeek R1 on many local
+// servers), write their reasoning as `<think>…</think>` ahead of the answer.
+// Streamed chunks can cut a tag in half, so a possible partial tag at the
+// end of a chunk is held back until the next one arrives.
+
+use super::StreamSink;
+
+const OPEN_TAG: &str = "<think>";
+const CLOSE_TAG: &str = "</think>";
+
+#[derive(Debug, PartialEq)]
+pub enum Segment {
+    Reasoning(String),
+    Text(String),
+}
+
+#[derive(Default)]
+pub struct ThinkSplitter {
+    in_think: bool,
+    // Drop the blank lines models put between reasoning and answer
+    trim_text: bool,
+    pending: String,
+}
+
+impl ThinkSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Splits the next chunk into reasoning and answer text.
+    pub fn push(&mut self, chunk: &str) -> Vec<Segment> {
+        self.pending.push_str(chunk);
+        let mut segments = Vec::new();
+
+        loop {
+            let tag = if self.in_think { CLOSE_TAG } else { OPEN_TAG };
+            if let Some(pos) = self.pending.find(tag) {
+                let before: String = self.pending.drain(..pos + tag.len()).collect();
+                self.emit(&mut segments, &before[..pos]);
+                self.in_think = !self.in_think;
+                self.trim_text = !self.in_think;
+                continue;
+            }
+
+            // Tags are ASCII, so the held back suffix starts on a char boundary
+            let held = (1..tag.len())
+                .rev()
+                .find(|len| self.pending.ends_with(&tag[..*len]))
+                .unwrap_or(0);
+            let ready: String = self.pending.drain(..self.pending.len() - held).collect();
+            self.emit(&mut segments, &ready);
+            return segments;
+        }
+    }
+
+    /// Flushes text held back at the end of the stream.
+    pub fn finish(&mut self) -> Vec<Segment> {
+        let rest = std::mem::take(&mut self.pending);
+        let mut segments = Vec::new();
+        self.emit(&mut segments, &rest);
+        segments
+    }
+
+    fn emit(&mut self, segments: &mut Vec<Segment>, text: &str) {
+        if self.in_think {
+            if !text.is_empty() {
+                segments.push(Segment::Reasoning(text.to_string()));
+            }
+            return;
+        }
+
+        let text = if self.trim_text {
+            text.trim_start()
+        } else {
+            text
+        };
+        if !text.is_empty() {
+            self.trim_text = false;
+            segments.push(Segment::Text(text.to_string()));
+        }
+    }
+}
+
+/// Appends segments to the answer and the reasoning, streaming each to `sink`.
+pub fn stream_segments(
+    segments: Vec<Segment>,
+    sink: &StreamSink,
+    content: &mut String,
+    reasoning: &mut String,
+) {
+    for segment in segments {
+        match segment {
+            Segment::Reasoning(text) => {
+                reasoning.push_str(&text);
+                sink.reasoning(&text);
+            }
+            Segment::Text(text) => {
+                content.push_str(&text);
+                sink.text(&text);
+            }
+        }
+    }
+}
+
+/// Splits a complete answer into its text and reasoning, if it had any.
+pub fn split_think(text: &str) -> (String, Option<String>) {
+    let mut splitter = ThinkSplitter::new();
+    let mut segments = splitter.push(text);
+    segments.extend(splitter.finish());
+
+    let mut content = String::new();
+    let mut reasoning = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Reasoning(text) => reasoning.push_str(&text),
+            Segment::Text(text) => content.push_str(&text),
+        }
+    }
+    (content, (!reasoning.is_empty()).then_some(reasoning))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_tags_cut_across_chunks() {
+        let mut splitter = ThinkSplitter::new();
+        let mut segments = Vec::new();
+        for chunk in ["<thi", "nk>Let me", " check.</th", "ink>\n\nIt is ", "42."] {
+            segments.extend(splitter.push(chunk));
+        }
+        segments.extend(splitter.finish());
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Reasoning("Let me".to_string()),
+                Segment::Reasoning(" check.".to_string()),
+                Segment::Text("It is ".to_string()),
+                Segment::Text("42.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_text_without_tags_alone() {
+        let (content, reasoning) = split_think("a < b, and <b>bold</b> <thin");
+
+        assert_eq!(content, "a < b, and <b>bold</b> <thin");
+        assert_eq!(reasoning, None);
+    }
+}
@@ -0,0 +1,423 @@
+// Client-side tools run by the backend on a model's behalf
+//
+// Claude and Ollama models call these when a request opts into client tools.
+// Results are JSON strings, including the `_component` hint the UI uses to
+// render a weather card.
+
+use log::{error, info};
+use serde::Serialize;
+use serde_json::json;
+use tauri_plugin_calendar::{CalendarExt, FetchEventsRequest};
+
+const USER_AGENT: &str = "Olly Weather App/1.0";
+
+/// Tool schema in the format the Claude Messages API takes.
+#[derive(Serialize, Clone, Debug)]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input_schema: serde_json::Value,
+}
+
+/// Result handed back to the model; errors are still JSON it can explain.
+#[derive(Serialize, Clone, Debug)]
+pub struct ToolOutput {
+    pub content: String,
+    pub is_error: bool,
+}
+
+impl ToolOutput {
+    fn ok(value: serde_json::Value) -> Self {
+        Self {
+            content: value.to_string(),
+            is_error: false,
+        }
+    }
+
+    fn error(error: &str, message: impl Into<String>) -> Self {
+        Self {
+            content: json!({ "error": error, "message": message.into() }).to_string(),
+            is_error: true,
+        }
+    }
+}
+
+pub fn definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "getCalendarEvents",
+            description: "Fetch upcoming calendar events from the user's macOS Calendar. Use this when the user asks about their schedule, meetings, appointments, or what's on their calendar. If the user ask involves traveling addtional can use getWeather tool to determine good or bad travel conditions.",
+            input_schema: json!({
+                "type": "object",
+                "required": ["daysAhead"],
+                "properties": {
+                    "daysAhead": {
+                        "type": "integer",
+                        "description": "Number of days ahead to fetch events. Default is 14 days (2 weeks). Use 1 for today, 7 for this week, etc.",
+                        "minimum": 1,
+                        "maximum": 90
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "getRickAndMortyEpisode",
+            description: "Fetch fun facts about Rick and Morty episodes. Use this when the user asks about Rick and Morty, wants to know about a specific episode, or wants a random episode fun fact.",
+            input_schema: json!({
+                "type": "object",
+                "required": [],
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Search for episodes by name (e.g., \"Pickle Rick\", \"Lawnmower Dog\"). Leave empty to get a random episode."
+                    },
+                    "episode": {
+                        "type": "string",
+                        "description": "Filter by episode code (e.g., \"S01E01\", \"S03E03\"). Leave empty to search by name or get a random episode."
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "getWeather",
+            description: "Get weather forecast for a specific location. Returns current weather or multi-day forecast based on user intent. Use this when the user asks about weather, temperature, forecast, or conditions for any location.",
+            input_schema: json!({
+                "type": "object",
+                "required": ["location"],
+                "properties": {
+                    "location": {
+                        "type": "string",
+                        "description": "Location in \"City, State\" format (e.g., \"Boston, MA\" or \"New York, NY\"). Can also accept full city names like \"San Francisco, California\"."
+                    },
+                    "days": {
+                        "type": "integer",
+                        "description": "Number of forecast days to return. Use 1 for current/today only, 2-7 for multi-day forecast. Default is 1 (current weather only).",
+                        "minimum": 1,
+                        "maximum": 7
+                    }
+                }
+            }),
+        },
+    ]
+}
+
+pub async fn execute(app: &tauri::AppHandle, name: &str, input: &serde_json::Value) -> ToolOutput {
+    info!("Executing tool {} with input {}", name, input);
+    match name {
+        "getCalendarEvents" => get_calendar_events(app, input),
+        "getWeather" => get_weather(input).await,
+        "getRickAndMortyEpisode" => get_rick_and_morty_episode(input).await,
+        _ => ToolOutput::error("Unknown tool", format!("No tool named {}", name)),
+    }
+}
+
+fn get_calendar_events(app: &tauri::AppHandle, input: &serde_json::Value) -> ToolOutput {
+    let days_ahead = input
+        .get("daysAhead")
+        .and_then(|d| d.as_i64())
+        .unwrap_or(14)
+        .clamp(1, 90) as i32;
+    let calendar = app.calendar();
+
+    let permission = match calendar.check_permission() {
+        Ok(status) => status,
+        Err(e) => return ToolOutput::error("Failed to fetch calendar events", e.to_string()),
+    };
+    match permission.as_str() {
+        "authorized" => {}
+        "prompt" => match calendar.request_permission() {
+            Ok(response) if response.granted => {}
+            Ok(_) => {
+                return ToolOutput::error(
+                    "Calendar access denied",
+                    "Calendar access was denied. Please go to System Settings > Privacy & Security > Calendars and enable access for the Olly app, then try again.",
+                )
+            }
+            Err(e) => return ToolOutput::error("Failed to fetch calendar events", e.to_string()),
+        },
+        "denied" => {
+            return ToolOutput::error(
+                "Calendar access denied",
+                "Calendar access is currently denied. Please go to System Settings > Privacy & Security > Calendars and enable access for the Olly app.",
+            )
+        }
+        status => {
+            return ToolOutput::error(
+                "Calendar access not granted",
+                format!(
+                    "Calendar permission status: {}. Please check System Settings > Privacy & Security > Calendars.",
+                    status
+                ),
+            )
+        }
+    }
+
+    let events = match calendar.fetch_events(FetchEventsRequest { days_ahead }) {
+        Ok(response) => response.events,
+        Err(e) => {
+            error!("Error fetching calendar events: {}", e);
+            return ToolOutput::error("Failed to fetch calendar events", e.to_string());
+        }
+    };
+
+    if events.is_empty() {
+        return ToolOutput::ok(json!({
+            "message": format!("No events found in the next {} days.", days_ahead),
+            "events": []
+        }));
+    }
+
+    let recurring = events.iter().filter(|e| e.is_recurring).count();
+    let formatted: Vec<serde_json::Value> = events
+        .iter()
+        .map(|event| {
+            json!({
+                "title": event.title,
+                "startDate": event.start_date,
+                "endDate": event.end_date,
+                "location": event.location,
+                "notes": event.notes,
+                "isAllDay": event.is_all_day,
+                "isRecurring": event.is_recurring,
+                "calendar": event.calendar_title
+            })
+        })
+        .collect();
+
+    ToolOutput::ok(json!({
+        "message": format!(
+            "Found {} event(s) in the next {} days: {} recurring and {} one-time events. When summarizing, make sure to include BOTH recurring and one-time events. Format any event data you show using code blocks for better readability.",
+            events.len(), days_ahead, recurring, events.len() - recurring
+        ),
+        "daysAhead": days_ahead,
+        "totalEvents": events.len(),
+        "recurringCount": recurring,
+        "oneTimeCount": events.len() - recurring,
+        "events": formatted
+    }))
+}
+
+async fn get_rick_and_morty_episode(input: &serde_json::Value) -> ToolOutput {
+    let params: Vec<(&str, &str)> = ["name", "episode"]
+        .into_iter()
+        .filter_map(|key| {
+            let value = input.get(key).and_then(|v| v.as_str())?;
+            (!value.is_empty()).then_some((key, value))
+        })
+        .collect();
+
+    match fetch_episode(&params).await {
+        Ok(output) => output,
+        Err(e) => {
+            error!("Error fetching Rick and Morty episode: {}", e);
+            ToolOutput::error("Failed to fetch episode data", e)
+        }
+    }
+}
+
+async fn fetch_episode(params: &[(&str, &str)]) -> Result<ToolOutput, String> {
+    let url = reqwest::Url::parse_with_params("https://rickandmortyapi.com/api/episode", params)
+        .map_err(|e| e.to_string())?;
+    let response = get_json(&reqwest::Client::new(), url.as_str()).await?;
+    if response.status() == 404 {
+        return Ok(ToolOutput::error(
+            "No episodes found",
+            "No episodes found matching that search.",
+        ));
+    }
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+    let data = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Searches return { results: [...] }
+    let episodes = match data.get("results").and_then(|r| r.as_array()) {
+        Some(results) => results.clone(),
+        None => vec![data],
+    };
+    if episodes.is_empty() {
+        return Ok(ToolOutput::error(
+            "No episodes found",
+            "No episodes found matching that search.",
+        ));
+    }
+
+    // Pick one of the matches at random, for variety
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as usize)
+        .unwrap_or_default();
+    let pick = &episodes[nanos % episodes.len()];
+    let name = pick["name"].as_str().unwrap_or_default();
+    let episode = pick["episode"].as_str().unwrap_or_default();
+    let air_date = pick["air_date"].as_str().unwrap_or_default();
+    let character_count = pick["characters"].as_array().map_or(0, |c| c.len());
+
+    Ok(ToolOutput::ok(json!({
+        "name": name,
+        "episode": episode,
+        "air_date": air_date,
+        "character_count": character_count,
+        "url": pick["url"],
+        "total_results": episodes.len(),
+        "message": format!(
+            "Share fun facts about the Rick and Morty episode \"{}\" ({}), which aired on {} and features {} characters. Be enthusiastic and entertaining!",
+            name, episode, air_date, character_count
+        )
+    })))
+}
+
+async fn get_weather(input: &serde_json::Value) -> ToolOutput {
+    let Some(location) = input.get("location").and_then(|l| l.as_str()) else {
+        return ToolOutput::error("Missing location", "The location argument is required.");
+    };
+    let days = input
+        .get("days")
+        .and_then(|d| d.as_u64())
+        .unwrap_or(1)
+        .clamp(1, 7) as usize;
+
+    match fetch_forecast(location, days).await {
+        Ok(output) => output,
+        Err(e) => {
+            error!("Error fetching weather data: {}", e);
+            ToolOutput {
+                content: json!({
+                    "error": "Failed to fetch weather data",
+                    "message": e,
+                    "details": "Please check the location format and try again. Use 'City, State' format for US locations."
+                })
+                .to_string(),
+                is_error: true,
+            }
+        }
+    }
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<reqwest::Response, String> {
+    client
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))
+}
+
+/// Geocodes with Nominatim, then reads the weather.gov forecast (US only).
+async fn fetch_forecast(location: &str, days: usize) -> Result<ToolOutput, String> {
+    let client = reqwest::Client::new();
+
+    let url = reqwest::Url::parse_with_params(
+        "https://nominatim.openstreetmap.org/search",
+        &[("q", location), ("format", "json")],
+    )
+    .map_err(|e| e.to_string())?;
+    let response = get_json(&client, url.as_str()).await?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to fetch coordinates: {}",
+            response.status()
+        ));
+    }
+    let places = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let Some(place) = places.first() else {
+        return Ok(ToolOutput::error(
+            "Location not found",
+            format!("Could not find coordinates for \"{}\". Please try a different location format like \"City, State\" (e.g., \"Boston, MA\").", location),
+        ));
+    };
+    let lat = place
+        .get("lat")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let lon = place
+        .get("lon")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let display_name = place
+        .get("display_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(location);
+
+    let response = get_json(
+        &client,
+        &format!("https://api.weather.gov/points/{},{}", lat, lon),
+    )
+    .await?;
+    if response.status() == 404 {
+        return Ok(ToolOutput::error(
+            "Location not supported",
+            format!("Weather.gov only provides forecasts for US locations. \"{}\" appears to be outside the US coverage area.", location),
+        ));
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Weather.gov points API error: {}",
+            response.status()
+        ));
+    }
+    let points = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| e.to_string())?;
+    let forecast_url = points["properties"]["forecast"]
+        .as_str()
+        .ok_or("Invalid weather.gov points response structure")?;
+
+    let response = get_json(&client, forecast_url).await?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Weather.gov forecast API error: {}",
+            response.status()
+        ));
+    }
+    let forecast = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| e.to_string())?;
+    let periods = forecast["properties"]["periods"]
+        .as_array()
+        .ok_or("Invalid weather.gov forecast response structure")?;
+
+    // Each day has a day and a night period
+    let formatted: Vec<serde_json::Value> = periods
+        .iter()
+        .take(days * 2)
+        .map(|period| {
+            json!({
+                "name": period["name"],
+                "temperature": period["temperature"],
+                "temperatureUnit": period["temperatureUnit"],
+                "isDaytime": period["isDaytime"],
+                "windSpeed": period["windSpeed"],
+                "windDirection": period["windDirection"],
+                "shortForecast": period["shortForecast"],
+                "detailedForecast": period["detailedForecast"],
+                "precipitationProbability": period["probabilityOfPrecipitation"]["value"].as_i64().unwrap_or(0)
+            })
+        })
+        .collect();
+
+    let message = if days == 1 {
+        format!("Weather forecast for {} is displayed above in a visual card. Do NOT repeat the weather details - just acknowledge the forecast is shown, write a short summary sentence for all days requested and offer to help with anything else.", display_name)
+    } else {
+        format!("{}-day weather forecast for {} is displayed above in a visual card. Do NOT repeat the weather details - just acknowledge the forecast is shown and offer to help with anything else.", days, display_name)
+    };
+
+    Ok(ToolOutput::ok(json!({
+        "_component": "WeatherCard",
+        "message": message,
+        "location": display_name,
+        "coordinates": { "lat": lat, "lon": lon },
+        "days": days,
+        "periodsReturned": formatted.len(),
+        "forecast": formatted
+    })))
+}
@@ -0,0 +1,440 @@
+// Token usage and cost ledger
+//
+// Every finished chat turn appends one JSON line to ~/.olly/usage.jsonl with
+// the token counts the provider reported. Cost is priced when the turn is
+// recorded, so later price changes don't rewrite history. Models missing
+// from the price table (local endpoints, new releases) are recorded without
+// a cost. Streams stopped before the provider reported usage are recorded
+// with an estimate, flagged as such, since the tokens are billed anyway.
+
+use chrono::{DateTime, Duration, Local, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::providers::Usage;
+
+/// USD per million tokens.
+struct Price {
+    input: f64,
+    output: f64,
+    cache_write: f64,
+    cache_read: f64,
+}
+
+const fn price(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Price {
+    Price {
+        input,
+        output,
+        cache_write,
+        cache_read,
+    }
+}
+
+// Matched by the longest model id prefix within the provider
+const PRICES: &[(&str, &str, Price)] = &[
+    ("claude", "claude-opus-4-5", price(5.0, 25.0, 6.25, 0.5)),
+    ("claude", "claude-opus-4", price(15.0, 75.0, 18.75, 1.5)),
+    ("claude", "claude-3-opus", price(15.0, 75.0, 18.75, 1.5)),
+    ("claude", "claude-sonnet-4", price(3.0, 15.0, 3.75, 0.3)),
+    ("claude", "claude-3-7-sonnet", price(3.0, 15.0, 3.75, 0.3)),
+    ("claude", "claude-3-5-sonnet", price(3.0, 15.0, 3.75, 0.3)),
+    ("claude", "claude-haiku-4-5", price(1.0, 5.0, 1.25, 0.1)),
+    ("claude", "claude-3-5-haiku", price(0.8, 4.0, 1.0, 0.08)),
+    ("claude", "claude-3-haiku", price(0.25, 1.25, 0.3, 0.03)),
+    ("openai", "gpt-5-nano", price(0.05, 0.4, 0.05, 0.005)),
+    ("openai", "gpt-5-mini", price(0.25, 2.0, 0.25, 0.025)),
+    ("openai", "gpt-5", price(1.25, 10.0, 1.25, 0.125)),
+    ("openai", "gpt-4.1-nano", price(0.1, 0.4, 0.1, 0.025)),
+    ("openai", "gpt-4.1-mini", price(0.4, 1.6, 0.4, 0.1)),
+    ("openai", "gpt-4.1", price(2.0, 8.0, 2.0, 0.5)),
+    ("openai", "gpt-4o-mini", price(0.15, 0.6, 0.15, 0.075)),
+    ("openai", "gpt-4o", price(2.5, 10.0, 2.5, 1.25)),
+    ("openai", "o4-mini", price(1.1, 4.4, 1.1, 0.275)),
+    ("openai", "o3-mini", price(1.1, 4.4, 1.1, 0.55)),
+    ("openai", "o3", price(2.0, 8.0, 2.0, 0.5)),
+    (
+        "perplexity",
+        "sonar-deep-research",
+        price(2.0, 8.0, 2.0, 2.0),
+    ),
+    (
+        "perplexity",
+        "sonar-reasoning-pro",
+        price(2.0, 8.0, 2.0, 2.0),
+    ),
+    ("perplexity", "sonar-reasoning", price(1.0, 5.0, 1.0, 1.0)),
+    ("perplexity", "sonar-pro", price(3.0, 15.0, 3.0, 3.0)),
+    ("perplexity", "sonar", price(1.0, 1.0, 1.0, 1.0)),
+    ("gemini", "gemini-2.5-pro", price(1.25, 10.0, 1.25, 0.31)),
+    (
+        "gemini",
+        "gemini-2.5-flash-lite",
+        price(0.1, 0.4, 0.1, 0.025),
+    ),
+    ("gemini", "gemini-2.5-flash", price(0.3, 2.5, 0.3, 0.075)),
+    (
+        "gemini",
+        "gemini-2.0-flash-lite",
+        price(0.075, 0.3, 0.075, 0.075),
+    ),
+    ("gemini", "gemini-2.0-flash", price(0.1, 0.4, 0.1, 0.025)),
+];
+
+/// Cost of a turn in USD, if the model is priced. Local models are free.
+fn cost(provider: &str, model: &str, usage: &Usage) -> Option<f64> {
+    if provider == "ollama" {
+        return Some(0.0);
+    }
+
+    let (_, _, price) = PRICES
+        .iter()
+        .filter(|(p, prefix, _)| *p == provider && model.starts_with(prefix))
+        .max_by_key(|(_, prefix, _)| prefix.len())?;
+
+    let per_token = |tokens: u64, usd_per_million: f64| tokens as f64 * usd_per_million / 1e6;
+    Some(
+        per_token(usage.input_tokens, price.input)
+            + per_token(usage.output_tokens, price.output)
+            + per_token(usage.cache_creation_tokens, price.cache_write)
+            + per_token(usage.cache_read_tokens, price.cache_read),
+    )
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    #[serde(flatten)]
+    pub usage: Usage,
+    #[serde(default)]
+    pub cost: Option<f64>,
+    // Counted from the text of a cancelled stream, not reported
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub estimated: bool,
+}
+
+fn get_ledger_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".olly").join("usage.jsonl")
+}
+
+/// Appends a finished turn to the ledger. Failures are logged, never
+/// surfaced, so they can't break a chat.
+pub fn record(
+    provider: &str,
+    model: &str,
+    conversation_id: Option<&str>,
+    usage: &Usage,
+    estimated: bool,
+) {
+    let record = UsageRecord {
+        timestamp: Utc::now(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        conversation_id: conversation_id.map(|id| id.to_string()),
+        usage: usage.clone(),
+        cost: cost(provider, model, usage),
+        estimated,
+    };
+
+    if let Err(e) = append(&record) {
+        error!("Failed to record usage: {}", e);
+    }
+}
+
+fn append(record: &UsageRecord) -> Result<(), String> {
+    let path = get_ledger_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let line = serde_json::to_string(record)
+        .map_err(|e| format!("Failed to serialize usage record: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open usage ledger: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write usage ledger: {}", e))
+}
+
+fn load_records() -> Vec<UsageRecord> {
+    let Ok(contents) = fs::read_to_string(get_ledger_path()) else {
+        return Vec::new();
+    };
+
+    // Skip lines that don't parse, e.g. one cut short by a crash
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Totals for one group of a report.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct UsageTotals {
+    pub key: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost: f64,
+    // Requests for models without a price, left out of `cost`
+    pub unpriced_requests: u64,
+    // Cancelled requests whose tokens were estimated
+    pub estimated_requests: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.input_tokens += record.usage.input_tokens;
+        self.output_tokens += record.usage.output_tokens;
+        self.cache_creation_tokens += record.usage.cache_creation_tokens;
+        self.cache_read_tokens += record.usage.cache_read_tokens;
+        match record.cost {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced_requests += 1,
+        }
+        if record.estimated {
+            self.estimated_requests += 1;
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UsageReport {
+    pub range: String,
+    pub group_by: String,
+    pub groups: Vec<UsageTotals>,
+    pub total: UsageTotals,
+}
+
+/// Start of a report range: "today", "7d", "30d" (or any "<n>d") and "all".
+fn range_start(range: &str) -> Result<Option<DateTime<Utc>>, String> {
+    let today = Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    match range {
+        "all" => Ok(None),
+        "today" => Ok(Some(today)),
+        _ => {
+            let days = range
+                .strip_suffix('d')
+                .and_then(|days| days.parse::<i64>().ok())
+                .filter(|days| *days > 0)
+                .ok_or_else(|| format!("Invalid usage range: {}", range))?;
+            Ok(Some(today - Duration::days(days - 1)))
+        }
+    }
+}
+
+fn group_key(record: &UsageRecord, group_by: &str) -> Result<String, String> {
+    match group_by {
+        "day" => Ok(record
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d")
+            .to_string()),
+        "model" => Ok(format!("{}/{}", record.provider, record.model)),
+        "provider" => Ok(record.provider.clone()),
+        "conversation" => Ok(record
+            .conversation_id
+            .clone()
+            .unwrap_or_else(|| "none".to_string())),
+        _ => Err(format!("Invalid usage grouping: {}", group_by)),
+    }
+}
+
+pub fn build_report(
+    records: &[UsageRecord],
+    range: &str,
+    group_by: &str,
+) -> Result<UsageReport, String> {
+    let start = range_start(range)?;
+    let mut groups: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    let mut total = UsageTotals {
+        key: "total".to_string(),
+        ..Default::default()
+    };
+
+    for record in records
+        .iter()
+        .filter(|record| start.is_none_or(|start| record.timestamp >= start))
+    {
+        let key = group_key(record, group_by)?;
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| UsageTotals {
+                key,
+                ..Default::default()
+            })
+            .add(record);
+        total.add(record);
+    }
+
+    Ok(UsageReport {
+        range: range.to_string(),
+        group_by: group_by.to_string(),
+        groups: groups.into_values().collect(),
+        total,
+    })
+}
+
+// Commands
+
+/// Usage over `range` ("today", "7d", "30d", "all"; default "30d"), grouped
+/// by "day", "model", "provider" or "conversation" (default "day").
+#[tauri::command]
+pub async fn get_usage_report(
+    range: Option<String>,
+    group_by: Option<String>,
+) -> Result<UsageReport, String> {
+    let range = range.unwrap_or_else(|| "30d".to_string());
+    let group_by = group_by.unwrap_or_else(|| "day".to_string());
+    info!("Building usage report for {} by {}", range, group_by);
+
+    build_report(&load_records(), &range, &group_by)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u64, output_tokens: u64) -> Usage {
+        Usage {
+            input_tokens,
+            output_tokens,
+            ..Default::default()
+        }
+    }
+
+    fn record(days_ago: i64, provider: &str, model: &str, conversation: &str) -> UsageRecord {
+        let usage = usage(1000, 100);
+        UsageRecord {
+            timestamp: Utc::now() - Duration::days(days_ago),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            conversation_id: Some(conversation.to_string()),
+            cost: cost(provider, model, &usage),
+            usage,
+            estimated: false,
+        }
+    }
+
+    fn keys(report: &UsageReport) -> Vec<&str> {
+        report.groups.iter().map(|g| g.key.as_str()).collect()
+    }
+
+    #[test]
+    fn prices_by_longest_model_prefix() {
+        let million = usage(1_000_000, 1_000_000);
+
+        // gpt-4o-mini, not gpt-4o
+        assert_eq!(
+            cost("openai", "gpt-4o-mini-2024-07-18", &million),
+            Some(0.75)
+        );
+        assert_eq!(cost("openai", "gpt-4o-2024-08-06", &million), Some(12.5));
+        // Caching is billed at its own rates
+        let cached = Usage {
+            cache_creation_tokens: 1_000_000,
+            cache_read_tokens: 1_000_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            cost("claude", "claude-sonnet-4-20250514", &cached),
+            Some(4.05)
+        );
+        // The prefix must match within the provider
+        assert_eq!(cost("openai", "claude-sonnet-4-20250514", &million), None);
+        assert_eq!(cost("endpoint:lan", "llama3", &million), None);
+    }
+
+    #[test]
+    fn ollama_is_free() {
+        assert_eq!(cost("ollama", "llama3.2:3b", &usage(5000, 800)), Some(0.0));
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let now = Utc::now();
+        assert_eq!(range_start("all"), Ok(None));
+
+        let today = range_start("today").unwrap().unwrap();
+        assert!(today <= now && now - today < Duration::days(1));
+        assert_eq!(range_start("1d"), Ok(Some(today)));
+        assert_eq!(range_start("7d"), Ok(Some(today - Duration::days(6))));
+
+        for range in ["0d", "-3d", "7", "week", ""] {
+            assert!(range_start(range).is_err(), "{} should be rejected", range);
+        }
+    }
+
+    #[test]
+    fn groups_records() {
+        let records = [
+            record(0, "claude", "claude-haiku-4-5", "a"),
+            record(0, "ollama", "llama3.2:3b", "a"),
+            record(1, "claude", "claude-haiku-4-5", "b"),
+            record(40, "openai", "gpt-4o", "c"),
+        ];
+
+        let by_provider = build_report(&records, "30d", "provider").unwrap();
+        assert_eq!(keys(&by_provider), ["claude", "ollama"]);
+        assert_eq!(by_provider.groups[0].requests, 2);
+        assert_eq!(by_provider.total.requests, 3);
+        assert_eq!(by_provider.total.input_tokens, 3000);
+
+        let by_model = build_report(&records, "all", "model").unwrap();
+        assert_eq!(
+            keys(&by_model),
+            [
+                "claude/claude-haiku-4-5",
+                "ollama/llama3.2:3b",
+                "openai/gpt-4o"
+            ]
+        );
+
+        let by_conversation = build_report(&records, "all", "conversation").unwrap();
+        assert_eq!(keys(&by_conversation), ["a", "b", "c"]);
+
+        let by_day = build_report(&records, "all", "day").unwrap();
+        assert_eq!(by_day.groups.len(), 3);
+        assert_eq!(by_day.groups[2].requests, 2);
+
+        assert!(build_report(&records, "all", "week").is_err());
+    }
+
+    #[test]
+    fn totals_count_unpriced_and_estimated_requests() {
+        let cancelled = UsageRecord {
+            estimated: true,
+            ..record(0, "ollama", "llama3.2:3b", "a")
+        };
+        let records = [
+            record(0, "claude", "claude-haiku-4-5", "a"),
+            record(0, "endpoint:lan", "mistral", "a"),
+            cancelled,
+        ];
+
+        let report = build_report(&records, "today", "provider").unwrap();
+        assert_eq!(report.total.unpriced_requests, 1);
+        assert_eq!(report.total.estimated_requests, 1);
+        assert!((report.total.cost - 0.0015).abs() < 1e-9);
+    }
+}
@@ -1,7 +1,29 @@
 import { Ollama } from "ollama/browser";
 import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
+import { invoke } from "@tauri-apps/api/core";
 export { Ollama }
 
+/**
+ * Address of the Ollama endpoint configured in the backend settings. Its
+ * credentials stay in the backend; chats go through backend commands.
+ * @returns {Promise<string>}
+ */
+export async function getOllamaHost() {
+  const { host } = await invoke("get_ollama_client_config");
+  return host;
+}
+
+/**
+ * Load an Ollama model into memory in the background, so the first reply
+ * doesn't wait for a cold load
+ * @param {string} model
+ */
+export function preloadOllamaModel(model) {
+  invoke("load_ollama_model", { model }).catch((error) => {
+    console.warn(`Failed to preload ${model}:`, error);
+  });
+}
+
 /**
  * Compress and resize an image to optimize for vision model processing
  * @param {File} file - The image file to compress
@@ -127,17 +149,15 @@ export async function compressImage(file, maxWidth = 1024, maxHeight = 1024, qua
 //needs to optimize this
 export async function getIcon(weather) {
 
-  const ollama = new Ollama({ host: "http://localhost:11434" });
-
-  const response = await ollama.chat({
+  const response = await invoke("chat", {
+    provider: "ollama",
     model: 'gemma3:1b',
-    "options": {
+    prompt: weather,
+    messages: [],
+    options: {
       //"seed": 101,
       "temperature": 0,
-    },
-    messages: [
-      {
-        role: 'system', content: `You job is to match a weather condition to an icon name in the list provided.  
+      system: `You job is to match a weather condition to an icon name in the list provided.  
       <instructions>
         -You will be provided with a weather condition and time of day (day or night) and will return an icon name from the list below that represents the weather condition and time of day provided. 
         - Match only to the icon names from the 'icon list'. 
@@ -165,14 +185,13 @@ export async function getIcon(weather) {
       - sad_face
       </icon list>
 
-        `},
-      { role: 'user', content: weather }
-    ],
+        `,
+    },
   })
-  console.log('respose', response.message.content)
+  console.log('respose', response.content)
   let iconName;
   try {
-    const content = response.message.content.trim();
+    const content = response.content.trim();
     let parsedContent;
 
     // First check if it's a plain string that matches a valid icon